@@ -36,6 +36,11 @@ export 'src/core/models/transaction_id.dart';
 export 'src/core/models/transactions_batch_info.dart';
 export 'src/core/models/transactions_batch_type.dart';
 export 'src/core/models/transactions_list.dart';
+export 'src/core/pending_messages/models/delivered_pending_message.dart';
+export 'src/core/pending_messages/models/failed_pending_message.dart';
+export 'src/core/pending_messages/models/pending_message.dart';
+export 'src/core/pending_messages/models/restored_pending_messages.dart';
+export 'src/core/pending_messages/pending_messages_storage.dart';
 export 'src/core/token_wallet/get_token_root_details.dart';
 export 'src/core/token_wallet/get_token_root_details_from_token_wallet.dart';
 export 'src/core/token_wallet/get_token_wallet_details.dart';
@@ -57,6 +62,7 @@ export 'src/core/ton_wallet/models/de_pool_on_round_complete_notification.dart';
 export 'src/core/ton_wallet/models/de_pool_receive_answer_notification.dart';
 export 'src/core/ton_wallet/models/existing_wallet_info.dart';
 export 'src/core/ton_wallet/models/known_payload.dart';
+export 'src/core/ton_wallet/models/message_flags.dart';
 export 'src/core/ton_wallet/models/multisig_confirm_transaction.dart';
 export 'src/core/ton_wallet/models/multisig_pending_transaction.dart';
 export 'src/core/ton_wallet/models/multisig_send_transaction.dart';
@@ -66,6 +72,7 @@ export 'src/core/ton_wallet/models/token_wallet_deployed_notification.dart';
 export 'src/core/ton_wallet/models/ton_wallet_details.dart';
 export 'src/core/ton_wallet/models/ton_wallet_transaction_with_data.dart';
 export 'src/core/ton_wallet/models/transaction_additional_info.dart';
+export 'src/core/ton_wallet/models/transfer_body.dart';
 export 'src/core/ton_wallet/models/wallet_interaction_info.dart';
 export 'src/core/ton_wallet/models/wallet_interaction_method.dart';
 export 'src/core/ton_wallet/ton_wallet.dart';
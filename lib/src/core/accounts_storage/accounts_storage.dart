@@ -1,6 +1,7 @@
 import 'dart:async';
 import 'dart:convert';
 import 'dart:ffi';
+import 'dart:isolate';
 
 import 'package:ffi/ffi.dart';
 import 'package:flutter/foundation.dart';
@@ -21,9 +22,12 @@ class AccountsStorage implements Finalizable {
 
   AccountsStorage._();
 
-  static Future<AccountsStorage> create(Storage storage) async {
+  static Future<AccountsStorage> create(
+    Storage storage, {
+    SendPort? onChangedPort,
+  }) async {
     final instance = AccountsStorage._();
-    await instance._initialize(storage);
+    await instance._initialize(storage, onChangedPort: onChangedPort);
     return instance;
   }
 
@@ -221,12 +225,17 @@ class AccountsStorage implements Finalizable {
     return result;
   }
 
-  Future<void> _initialize(Storage storage) => _updateEntries(() async {
+  Future<void> _initialize(
+    Storage storage, {
+    SendPort? onChangedPort,
+  }) =>
+      _updateEntries(() async {
         final storagePtr = storage.ptr;
 
         final result = await executeAsync(
           (port) => NekotonFlutter.instance().bindings.nt_accounts_storage_create(
                 port,
+                onChangedPort?.nativePort ?? 0,
                 storagePtr,
               ),
         );
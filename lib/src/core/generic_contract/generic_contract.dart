@@ -15,6 +15,8 @@ import 'package:nekoton_flutter/src/core/models/on_transactions_found_payload.da
 import 'package:nekoton_flutter/src/core/models/pending_transaction.dart';
 import 'package:nekoton_flutter/src/core/models/polling_method.dart';
 import 'package:nekoton_flutter/src/core/models/transaction.dart';
+import 'package:nekoton_flutter/src/core/pending_messages/models/restored_pending_messages.dart';
+import 'package:nekoton_flutter/src/core/pending_messages/pending_messages_storage.dart';
 import 'package:nekoton_flutter/src/core/utils.dart';
 import 'package:nekoton_flutter/src/crypto/models/signed_message.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
@@ -26,6 +28,7 @@ final _nativeFinalizer =
 class GenericContract extends ContractSubscription implements Finalizable {
   late final Pointer<Void> _ptr;
   final Transport _transport;
+  final PendingMessagesStorage? _pendingMessagesStorage;
   final _onMessageSentPort = ReceivePort();
   final _onMessageExpiredPort = ReceivePort();
   final _onStateChangedPort = ReceivePort();
@@ -36,14 +39,15 @@ class GenericContract extends ContractSubscription implements Finalizable {
   late final Stream<OnTransactionsFoundPayload> onTransactionsFoundStream;
   late final String _address;
 
-  GenericContract._(this._transport);
+  GenericContract._(this._transport, this._pendingMessagesStorage);
 
   static Future<GenericContract> subscribe({
     required Transport transport,
     required String address,
     required bool preloadTransactions,
+    PendingMessagesStorage? pendingMessagesStorage,
   }) async {
-    final instance = GenericContract._(transport);
+    final instance = GenericContract._(transport, pendingMessagesStorage);
     await instance._initialize(
       address: address,
       preloadTransactions: preloadTransactions,
@@ -136,12 +140,21 @@ class GenericContract extends ContractSubscription implements Finalizable {
     final pendingTransaction = await sendWithReliablePolling(() async {
       final signedMessageStr = jsonEncode(signedMessage);
 
+      final pendingMessagesStorage = _pendingMessagesStorage;
+
       final result = await executeAsync(
-        (port) => NekotonFlutter.instance().bindings.nt_generic_contract_send(
-              port,
-              ptr,
-              signedMessageStr.toNativeUtf8().cast<Char>(),
-            ),
+        (port) => pendingMessagesStorage != null
+            ? NekotonFlutter.instance().bindings.nt_generic_contract_send_persistent(
+                  port,
+                  ptr,
+                  pendingMessagesStorage.ptr,
+                  signedMessageStr.toNativeUtf8().cast<Char>(),
+                )
+            : NekotonFlutter.instance().bindings.nt_generic_contract_send(
+                  port,
+                  ptr,
+                  signedMessageStr.toNativeUtf8().cast<Char>(),
+                ),
       );
 
       final json = result as Map<String, dynamic>;
@@ -180,6 +193,30 @@ class GenericContract extends ContractSubscription implements Finalizable {
     return transaction;
   }
 
+  Future<RestoredPendingMessages> restorePendingMessages() async {
+    final pendingMessagesStorage = _pendingMessagesStorage;
+
+    if (pendingMessagesStorage == null) throw StateError('Pending messages storage is not set');
+
+    final transportPtr = _transport.ptr;
+    final transportTypeStr = jsonEncode(_transport.type.toString());
+
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_generic_contract_restore_pending_messages(
+            port,
+            ptr,
+            pendingMessagesStorage.ptr,
+            transportPtr,
+            transportTypeStr.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    final json = result as Map<String, dynamic>;
+    final restored = RestoredPendingMessages.fromJson(json);
+
+    return restored;
+  }
+
   @override
   Future<void> refresh() => executeAsync(
         (port) => NekotonFlutter.instance().bindings.nt_generic_contract_refresh(
@@ -242,6 +279,7 @@ class GenericContract extends ContractSubscription implements Finalizable {
 
     final transportPtr = _transport.ptr;
     final transportTypeStr = jsonEncode(_transport.type.toString());
+    final pendingMessagesStoragePtr = _pendingMessagesStorage?.ptr;
 
     final result = await executeAsync(
       (port) => NekotonFlutter.instance().bindings.nt_generic_contract_subscribe(
@@ -252,6 +290,7 @@ class GenericContract extends ContractSubscription implements Finalizable {
             _onTransactionsFoundPort.sendPort.nativePort,
             transportPtr,
             transportTypeStr.toNativeUtf8().cast<Char>(),
+            pendingMessagesStoragePtr ?? nullptr,
             address.toNativeUtf8().cast<Char>(),
             preloadTransactions ? 1 : 0,
           ),
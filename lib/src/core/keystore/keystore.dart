@@ -1,6 +1,7 @@
 import 'dart:async';
 import 'dart:convert';
 import 'dart:ffi';
+import 'dart:isolate';
 
 import 'package:ffi/ffi.dart';
 import 'package:flutter/foundation.dart';
@@ -40,12 +41,14 @@ class Keystore implements Finalizable {
     required Storage storage,
     LedgerConnection? ledgerConnection,
     required List<String> signers,
+    SendPort? onChangedPort,
   }) async {
     final instance = Keystore._();
     await instance._initialize(
       storage: storage,
       ledgerConnection: ledgerConnection,
       signers: signers,
+      onChangedPort: onChangedPort,
     );
     return instance;
   }
@@ -168,6 +171,7 @@ class Keystore implements Finalizable {
             signer.toNativeUtf8().cast<Char>(),
             inputStr.toNativeUtf8().cast<Char>(),
           ),
+      secret: true,
     );
 
     final json = result as Map<String, dynamic>;
@@ -250,6 +254,7 @@ class Keystore implements Finalizable {
             dataStr.toNativeUtf8().cast<Char>(),
             inputStr.toNativeUtf8().cast<Char>(),
           ),
+      secret: true,
     );
 
     final decryptedData = result as String;
@@ -407,6 +412,7 @@ class Keystore implements Finalizable {
     required Storage storage,
     LedgerConnection? ledgerConnection,
     required List<String> signers,
+    SendPort? onChangedPort,
   }) =>
       _updateEntries(() async {
         assert(!signers.contains(kLedgerKeySignerName) || ledgerConnection != null);
@@ -418,6 +424,7 @@ class Keystore implements Finalizable {
         final result = await executeAsync(
           (port) => NekotonFlutter.instance().bindings.nt_keystore_create(
                 port,
+                onChangedPort?.nativePort ?? 0,
                 storagePtr,
                 ledgerConnectionPtr ?? nullptr,
                 signersStr.toNativeUtf8().cast<Char>(),
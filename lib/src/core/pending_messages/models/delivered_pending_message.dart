@@ -0,0 +1,17 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/core/models/pending_transaction.dart';
+import 'package:nekoton_flutter/src/core/models/transaction.dart';
+
+part 'delivered_pending_message.freezed.dart';
+part 'delivered_pending_message.g.dart';
+
+@freezed
+class DeliveredPendingMessage with _$DeliveredPendingMessage {
+  const factory DeliveredPendingMessage({
+    required PendingTransaction pendingTransaction,
+    Transaction? transaction,
+  }) = _DeliveredPendingMessage;
+
+  factory DeliveredPendingMessage.fromJson(Map<String, dynamic> json) =>
+      _$DeliveredPendingMessageFromJson(json);
+}
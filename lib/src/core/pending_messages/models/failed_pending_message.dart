@@ -0,0 +1,16 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/core/pending_messages/models/pending_message.dart';
+
+part 'failed_pending_message.freezed.dart';
+part 'failed_pending_message.g.dart';
+
+@freezed
+class FailedPendingMessage with _$FailedPendingMessage {
+  const factory FailedPendingMessage({
+    required PendingMessage message,
+    required String error,
+  }) = _FailedPendingMessage;
+
+  factory FailedPendingMessage.fromJson(Map<String, dynamic> json) =>
+      _$FailedPendingMessageFromJson(json);
+}
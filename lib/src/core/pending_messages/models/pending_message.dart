@@ -0,0 +1,16 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'pending_message.freezed.dart';
+part 'pending_message.g.dart';
+
+@freezed
+class PendingMessage with _$PendingMessage {
+  const factory PendingMessage({
+    required String messageHash,
+    required int expireAt,
+    required String boc,
+    required String latestLt,
+  }) = _PendingMessage;
+
+  factory PendingMessage.fromJson(Map<String, dynamic> json) => _$PendingMessageFromJson(json);
+}
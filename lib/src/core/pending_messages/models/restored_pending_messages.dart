@@ -0,0 +1,20 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/core/models/pending_transaction.dart';
+import 'package:nekoton_flutter/src/core/pending_messages/models/delivered_pending_message.dart';
+import 'package:nekoton_flutter/src/core/pending_messages/models/failed_pending_message.dart';
+
+part 'restored_pending_messages.freezed.dart';
+part 'restored_pending_messages.g.dart';
+
+@freezed
+class RestoredPendingMessages with _$RestoredPendingMessages {
+  const factory RestoredPendingMessages({
+    required List<PendingTransaction> resent,
+    required List<DeliveredPendingMessage> delivered,
+    required List<PendingTransaction> expired,
+    required List<FailedPendingMessage> failed,
+  }) = _RestoredPendingMessages;
+
+  factory RestoredPendingMessages.fromJson(Map<String, dynamic> json) =>
+      _$RestoredPendingMessagesFromJson(json);
+}
@@ -0,0 +1,64 @@
+import 'dart:async';
+import 'dart:ffi';
+
+import 'package:ffi/ffi.dart';
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/core/pending_messages/models/pending_message.dart';
+import 'package:nekoton_flutter/src/external/storage.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+
+final _nativeFinalizer = NativeFinalizer(
+  NekotonFlutter.instance().bindings.addresses.nt_pending_messages_storage_free_ptr,
+);
+
+class PendingMessagesStorage implements Finalizable {
+  late final Pointer<Void> _ptr;
+
+  PendingMessagesStorage._();
+
+  static Future<PendingMessagesStorage> create(Storage storage) async {
+    final instance = PendingMessagesStorage._();
+    await instance._initialize(storage);
+    return instance;
+  }
+
+  Pointer<Void> get ptr => _ptr;
+
+  Future<List<PendingMessage>> entries(String address) async {
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_pending_messages_storage_entries(
+            port,
+            ptr,
+            address.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    final json = result as List<dynamic>;
+    final list = json.cast<Map<String, dynamic>>();
+    final entries = list.map((e) => PendingMessage.fromJson(e)).toList();
+
+    return entries;
+  }
+
+  Future<void> clear() => executeAsync(
+        (port) => NekotonFlutter.instance().bindings.nt_pending_messages_storage_clear(
+              port,
+              ptr,
+            ),
+      );
+
+  Future<void> _initialize(Storage storage) async {
+    final storagePtr = storage.ptr;
+
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_pending_messages_storage_create(
+            port,
+            storagePtr,
+          ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+}
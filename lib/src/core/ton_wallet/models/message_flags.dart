@@ -0,0 +1,16 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'message_flags.freezed.dart';
+part 'message_flags.g.dart';
+
+@freezed
+class MessageFlags with _$MessageFlags {
+  const factory MessageFlags({
+    @JsonKey(includeIfNull: false) bool? allBalance,
+    @JsonKey(includeIfNull: false) bool? payFeesSeparately,
+    @JsonKey(includeIfNull: false) bool? ignoreErrors,
+    @JsonKey(includeIfNull: false) bool? destroyIfZero,
+  }) = _MessageFlags;
+
+  factory MessageFlags.fromJson(Map<String, dynamic> json) => _$MessageFlagsFromJson(json);
+}
@@ -0,0 +1,16 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'transfer_body.freezed.dart';
+part 'transfer_body.g.dart';
+
+@Freezed(unionKey: 'type')
+class TransferBody with _$TransferBody {
+  const factory TransferBody.comment(String data) = _TransferBodyComment;
+
+  const factory TransferBody.boc(String data) = _TransferBodyBoc;
+
+  // Encrypted comment in a format specific to this library, other wallets can't read it
+  const factory TransferBody.encryptedComment(String data) = _TransferBodyEncryptedComment;
+
+  factory TransferBody.fromJson(Map<String, dynamic> json) => _$TransferBodyFromJson(json);
+}
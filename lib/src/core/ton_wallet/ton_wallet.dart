@@ -17,11 +17,15 @@ import 'package:nekoton_flutter/src/core/models/pending_transaction.dart';
 import 'package:nekoton_flutter/src/core/models/polling_method.dart';
 import 'package:nekoton_flutter/src/core/models/raw_contract_state.dart';
 import 'package:nekoton_flutter/src/core/models/transaction.dart';
+import 'package:nekoton_flutter/src/core/pending_messages/models/restored_pending_messages.dart';
+import 'package:nekoton_flutter/src/core/pending_messages/pending_messages_storage.dart';
 import 'package:nekoton_flutter/src/core/ton_wallet/models/existing_wallet_info.dart';
+import 'package:nekoton_flutter/src/core/ton_wallet/models/message_flags.dart';
 import 'package:nekoton_flutter/src/core/ton_wallet/models/multisig_pending_transaction.dart';
 import 'package:nekoton_flutter/src/core/ton_wallet/models/on_ton_wallet_transactions_found_payload.dart';
 import 'package:nekoton_flutter/src/core/ton_wallet/models/ton_wallet_details.dart';
 import 'package:nekoton_flutter/src/core/ton_wallet/models/ton_wallet_transaction_with_data.dart';
+import 'package:nekoton_flutter/src/core/ton_wallet/models/transfer_body.dart';
 import 'package:nekoton_flutter/src/core/utils.dart';
 import 'package:nekoton_flutter/src/crypto/models/signed_message.dart';
 import 'package:nekoton_flutter/src/crypto/unsigned_message.dart';
@@ -36,6 +40,7 @@ final _nativeFinalizer =
 class TonWallet extends ContractSubscription implements Finalizable {
   late final Pointer<Void> _ptr;
   final Transport _transport;
+  final PendingMessagesStorage? _pendingMessagesStorage;
   final _onMessageSentPort = ReceivePort();
   final _onMessageExpiredPort = ReceivePort();
   final _onStateChangedPort = ReceivePort();
@@ -59,15 +64,16 @@ class TonWallet extends ContractSubscription implements Finalizable {
   late final TonWalletDetails _details;
   RawContractState? _cachedContractState;
 
-  TonWallet._(this._transport);
+  TonWallet._(this._transport, this._pendingMessagesStorage);
 
   static Future<TonWallet> subscribe({
     required Transport transport,
     required int workchain,
     required String publicKey,
     required WalletType contract,
+    PendingMessagesStorage? pendingMessagesStorage,
   }) async {
-    final instance = TonWallet._(transport);
+    final instance = TonWallet._(transport, pendingMessagesStorage);
     await instance._subscribe(
       workchain: workchain,
       publicKey: publicKey,
@@ -79,8 +85,9 @@ class TonWallet extends ContractSubscription implements Finalizable {
   static Future<TonWallet> subscribeByAddress({
     required Transport transport,
     required String address,
+    PendingMessagesStorage? pendingMessagesStorage,
   }) async {
-    final instance = TonWallet._(transport);
+    final instance = TonWallet._(transport, pendingMessagesStorage);
     await instance._subscribeByAddress(address);
     return instance;
   }
@@ -88,8 +95,9 @@ class TonWallet extends ContractSubscription implements Finalizable {
   static Future<TonWallet> subscribeByExisting({
     required Transport transport,
     required ExistingWalletInfo existingWallet,
+    PendingMessagesStorage? pendingMessagesStorage,
   }) async {
-    final instance = TonWallet._(transport);
+    final instance = TonWallet._(transport, pendingMessagesStorage);
     await instance._subscribeByExisting(existingWallet);
     return instance;
   }
@@ -314,13 +322,17 @@ class TonWallet extends ContractSubscription implements Finalizable {
     required String publicKey,
     required String destination,
     required String amount,
-    String? body,
+    TransferBody? body,
+    String? stateInit,
+    MessageFlags? flags,
     required bool bounce,
     required Expiration expiration,
   }) async {
     final contractState = _cachedContractState ??= await _transport.getContractState(address);
 
     final contractStateStr = jsonEncode(contractState);
+    final bodyStr = body != null ? jsonEncode(body) : null;
+    final flagsStr = flags != null ? jsonEncode(flags) : null;
     final expirationStr = jsonEncode(expiration);
 
     final result = await executeAsync(
@@ -332,7 +344,9 @@ class TonWallet extends ContractSubscription implements Finalizable {
             destination.toNativeUtf8().cast<Char>(),
             amount.toNativeUtf8().cast<Char>(),
             bounce ? 1 : 0,
-            body?.toNativeUtf8().cast<Char>() ?? nullptr,
+            bodyStr?.toNativeUtf8().cast<Char>() ?? nullptr,
+            stateInit?.toNativeUtf8().cast<Char>() ?? nullptr,
+            flagsStr?.toNativeUtf8().cast<Char>() ?? nullptr,
             expirationStr.toNativeUtf8().cast<Char>(),
           ),
     );
@@ -388,12 +402,21 @@ class TonWallet extends ContractSubscription implements Finalizable {
     final pendingTransaction = await sendWithReliablePolling(() async {
       final signedMessageStr = jsonEncode(signedMessage);
 
+      final pendingMessagesStorage = _pendingMessagesStorage;
+
       final result = await executeAsync(
-        (port) => NekotonFlutter.instance().bindings.nt_ton_wallet_send(
-              port,
-              ptr,
-              signedMessageStr.toNativeUtf8().cast<Char>(),
-            ),
+        (port) => pendingMessagesStorage != null
+            ? NekotonFlutter.instance().bindings.nt_ton_wallet_send_persistent(
+                  port,
+                  ptr,
+                  pendingMessagesStorage.ptr,
+                  signedMessageStr.toNativeUtf8().cast<Char>(),
+                )
+            : NekotonFlutter.instance().bindings.nt_ton_wallet_send(
+                  port,
+                  ptr,
+                  signedMessageStr.toNativeUtf8().cast<Char>(),
+                ),
       );
 
       final json = result as Map<String, dynamic>;
@@ -412,6 +435,32 @@ class TonWallet extends ContractSubscription implements Finalizable {
     return transaction;
   }
 
+  Future<RestoredPendingMessages> restorePendingMessages() async {
+    final pendingMessagesStorage = _pendingMessagesStorage;
+
+    if (pendingMessagesStorage == null) throw StateError('Pending messages storage is not set');
+
+    final transportPtr = _transport.ptr;
+    final transportTypeStr = jsonEncode(_transport.type.toString());
+
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_ton_wallet_restore_pending_messages(
+            port,
+            ptr,
+            pendingMessagesStorage.ptr,
+            transportPtr,
+            transportTypeStr.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    final json = result as Map<String, dynamic>;
+    final restored = RestoredPendingMessages.fromJson(json);
+
+    _pendingTransactionsSubject.tryAdd(await _pendingTransactions);
+
+    return restored;
+  }
+
   @override
   Future<void> refresh() async {
     await executeAsync(
@@ -477,6 +526,7 @@ class TonWallet extends ContractSubscription implements Finalizable {
         () async {
           final transportPtr = _transport.ptr;
           final transportTypeStr = jsonEncode(_transport.type.toString());
+          final pendingMessagesStoragePtr = _pendingMessagesStorage?.ptr;
           final contractStr = jsonEncode(contract);
 
           final result = await executeAsync(
@@ -488,6 +538,7 @@ class TonWallet extends ContractSubscription implements Finalizable {
                   _onTransactionsFoundPort.sendPort.nativePort,
                   transportPtr,
                   transportTypeStr.toNativeUtf8().cast<Char>(),
+                  pendingMessagesStoragePtr ?? nullptr,
                   workchain,
                   publicKey.toNativeUtf8().cast<Char>(),
                   contractStr.toNativeUtf8().cast<Char>(),
@@ -502,6 +553,7 @@ class TonWallet extends ContractSubscription implements Finalizable {
         () async {
           final transportPtr = _transport.ptr;
           final transportTypeStr = jsonEncode(_transport.type.toString());
+          final pendingMessagesStoragePtr = _pendingMessagesStorage?.ptr;
 
           final result = await executeAsync(
             (port) => NekotonFlutter.instance().bindings.nt_ton_wallet_subscribe_by_address(
@@ -512,6 +564,7 @@ class TonWallet extends ContractSubscription implements Finalizable {
                   _onTransactionsFoundPort.sendPort.nativePort,
                   transportPtr,
                   transportTypeStr.toNativeUtf8().cast<Char>(),
+                  pendingMessagesStoragePtr ?? nullptr,
                   address.toNativeUtf8().cast<Char>(),
                 ),
           );
@@ -524,6 +577,7 @@ class TonWallet extends ContractSubscription implements Finalizable {
         () async {
           final transportPtr = _transport.ptr;
           final transportTypeStr = jsonEncode(_transport.type.toString());
+          final pendingMessagesStoragePtr = _pendingMessagesStorage?.ptr;
           final existingWalletStr = jsonEncode(existingWallet);
 
           final result = await executeAsync(
@@ -535,6 +589,7 @@ class TonWallet extends ContractSubscription implements Finalizable {
                   _onTransactionsFoundPort.sendPort.nativePort,
                   transportPtr,
                   transportTypeStr.toNativeUtf8().cast<Char>(),
+                  pendingMessagesStoragePtr ?? nullptr,
                   existingWalletStr.toNativeUtf8().cast<Char>(),
                 ),
           );
@@ -10,6 +10,7 @@ import 'package:nekoton_flutter/src/ffi_utils.dart';
 Keypair deriveFromPhrase({
   required List<String> phrase,
   required MnemonicType mnemonicType,
+  String? language,
 }) {
   final phraseStr = phrase.join(' ');
   final mnemonicTypeStr = jsonEncode(mnemonicType);
@@ -18,7 +19,9 @@ Keypair deriveFromPhrase({
     () => NekotonFlutter.instance().bindings.nt_derive_from_phrase(
           phraseStr.toNativeUtf8().cast<Char>(),
           mnemonicTypeStr.toNativeUtf8().cast<Char>(),
+          language?.toNativeUtf8().cast<Char>() ?? nullptr,
         ),
+    secret: true,
   );
 
   final json = result as Map<String, dynamic>;
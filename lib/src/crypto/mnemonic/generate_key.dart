@@ -7,13 +7,18 @@ import 'package:nekoton_flutter/src/crypto/mnemonic/models/generated_key.dart';
 import 'package:nekoton_flutter/src/crypto/mnemonic/models/mnemonic_type.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
 
-GeneratedKey generateKey(MnemonicType mnemonicType) {
+GeneratedKey generateKey(
+  MnemonicType mnemonicType, {
+  String? language,
+}) {
   final mnemonicTypeStr = jsonEncode(mnemonicType);
 
   final result = executeSync(
     () => NekotonFlutter.instance().bindings.nt_generate_key(
           mnemonicTypeStr.toNativeUtf8().cast<Char>(),
+          language?.toNativeUtf8().cast<Char>() ?? nullptr,
         ),
+    secret: true,
   );
 
   final json = result as Map<String, dynamic>;
@@ -4,10 +4,14 @@ import 'package:ffi/ffi.dart';
 import 'package:nekoton_flutter/src/bindings.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
 
-List<String> getHints(String input) {
+List<String> getHints(
+  String input, {
+  String? language,
+}) {
   final result = executeSync(
     () => NekotonFlutter.instance().bindings.nt_get_hints(
           input.toNativeUtf8().cast<Char>(),
+          language?.toNativeUtf8().cast<Char>() ?? nullptr,
         ),
   );
 
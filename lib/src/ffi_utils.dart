@@ -10,11 +10,11 @@ import 'package:nekoton_flutter/src/models/execution_result.dart';
 Pointer<Void> toPtrFromAddress(String address) =>
     NekotonFlutter.instance().bindings.nt_cstring_to_void_ptr(address.toNativeUtf8().cast<Char>());
 
-dynamic executeSync(Pointer<Char> Function() function) {
+dynamic executeSync(Pointer<Char> Function() function, {bool secret = false}) {
   final ptr = function();
   final string = ptr.cast<Utf8>().toDartString();
 
-  NekotonFlutter.instance().bindings.nt_free_cstring(ptr);
+  _freeCString(ptr, secret: secret);
 
   final json = jsonDecode(string) as Map<String, dynamic>;
   final executionResult = ExecutionResult.fromJson(json);
@@ -22,7 +22,7 @@ dynamic executeSync(Pointer<Char> Function() function) {
   return executionResult.handle();
 }
 
-Future<dynamic> executeAsync(void Function(int port) function) async {
+Future<dynamic> executeAsync(void Function(int port) function, {bool secret = false}) async {
   final receivePort = ReceivePort();
   final completer = Completer<dynamic>();
   final st = StackTrace.current;
@@ -31,7 +31,7 @@ Future<dynamic> executeAsync(void Function(int port) function) async {
     final ptr = toPtrFromAddress(data).cast<Char>();
     final string = ptr.cast<Utf8>().toDartString();
 
-    NekotonFlutter.instance().bindings.nt_free_cstring(ptr);
+    _freeCString(ptr, secret: secret);
 
     final json = jsonDecode(string) as Map<String, dynamic>;
     final executionResult = ExecutionResult.fromJson(json);
@@ -50,3 +50,7 @@ Future<dynamic> executeAsync(void Function(int port) function) async {
 
   return completer.future;
 }
+
+void _freeCString(Pointer<Char> ptr, {required bool secret}) => secret
+    ? NekotonFlutter.instance().bindings.nt_free_secret_cstring(ptr)
+    : NekotonFlutter.instance().bindings.nt_free_cstring(ptr);
@@ -0,0 +1,397 @@
+use std::sync::Arc;
+
+use nekoton::external::Storage;
+use tokio::sync::RwLock;
+
+use crate::{
+    core::accounts_storage::models::{
+        AccountGroup, AccountMetadata, AccountsMetadata, WatchOnlyAccount,
+    },
+    HandleError,
+};
+
+const STORAGE_KEY: &str = "__nekoton_flutter__accounts_metadata";
+const CURRENT_VERSION: u32 = 1;
+const GROUP_NOT_FOUND_ERROR: &str = "Group not found";
+const WATCH_ONLY_ACCOUNT_NOT_FOUND_ERROR: &str = "Watch-only account not found";
+
+pub struct AccountsMetadataStorage {
+    storage: Arc<dyn Storage>,
+    data: RwLock<AccountsMetadata>,
+}
+
+impl AccountsMetadataStorage {
+    pub async fn load(storage: Arc<dyn Storage>) -> Result<Self, String> {
+        let data = load_metadata(storage.as_ref()).await?;
+
+        Ok(Self {
+            storage,
+            data: RwLock::new(data),
+        })
+    }
+
+    pub async fn get(&self) -> AccountsMetadata {
+        self.data.read().await.clone()
+    }
+
+    pub async fn update_account(
+        &self,
+        account: String,
+        metadata: AccountMetadata,
+    ) -> Result<AccountMetadata, String> {
+        self.modify(|data| {
+            data.accounts.insert(account, metadata.clone());
+
+            Ok(metadata)
+        })
+        .await
+    }
+
+    pub async fn reorder_accounts(&self, order: Vec<String>) -> Result<(), String> {
+        self.modify(|data| {
+            data.order = order;
+
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn remove_accounts(&self, accounts: &[String]) -> Result<(), String> {
+        self.modify(|data| {
+            remove_accounts(data, accounts);
+
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn create_group(
+        &self,
+        name: String,
+        parent_id: Option<String>,
+    ) -> Result<AccountGroup, String> {
+        self.modify(|data| {
+            check_parent(data, None, parent_id.as_deref())?;
+
+            let group = AccountGroup {
+                id: hex::encode(rand::random::<[u8; 8]>()),
+                name,
+                parent_id,
+                accounts: Vec::new(),
+            };
+
+            data.groups.push(group.clone());
+
+            Ok(group)
+        })
+        .await
+    }
+
+    pub async fn rename_group(&self, id: &str, name: String) -> Result<AccountGroup, String> {
+        self.modify(|data| {
+            let group = find_group(data, id)?;
+            group.name = name;
+
+            Ok(group.clone())
+        })
+        .await
+    }
+
+    pub async fn move_group(
+        &self,
+        id: &str,
+        parent_id: Option<String>,
+    ) -> Result<AccountGroup, String> {
+        self.modify(|data| {
+            check_parent(data, Some(id), parent_id.as_deref())?;
+
+            let group = find_group(data, id)?;
+            group.parent_id = parent_id;
+
+            Ok(group.clone())
+        })
+        .await
+    }
+
+    pub async fn remove_group(
+        &self,
+        id: &str,
+    ) -> Result<(AccountGroup, Vec<AccountGroup>), String> {
+        self.modify(|data| {
+            let position = data
+                .groups
+                .iter()
+                .position(|e| e.id == id)
+                .ok_or(GROUP_NOT_FOUND_ERROR)
+                .handle_error()?;
+
+            let group = data.groups.remove(position);
+
+            let children = data
+                .groups
+                .iter_mut()
+                .filter(|e| e.parent_id.as_deref() == Some(id))
+                .map(|e| {
+                    e.parent_id = None;
+                    e.clone()
+                })
+                .collect::<Vec<_>>();
+
+            Ok((group, children))
+        })
+        .await
+    }
+
+    pub async fn add_accounts_to_group(
+        &self,
+        id: &str,
+        accounts: Vec<String>,
+    ) -> Result<Vec<AccountGroup>, String> {
+        self.modify(|data| {
+            find_group(data, id)?;
+
+            let mut updated_groups = Vec::new();
+
+            for group in &mut data.groups {
+                if group.id == id {
+                    for account in &accounts {
+                        if !group.accounts.contains(account) {
+                            group.accounts.push(account.clone());
+                        }
+                    }
+                    updated_groups.push(group.clone());
+                } else if group.accounts.iter().any(|e| accounts.contains(e)) {
+                    group.accounts.retain(|e| !accounts.contains(e));
+                    updated_groups.push(group.clone());
+                }
+            }
+
+            Ok(updated_groups)
+        })
+        .await
+    }
+
+    pub async fn remove_accounts_from_group(
+        &self,
+        id: &str,
+        accounts: Vec<String>,
+    ) -> Result<AccountGroup, String> {
+        self.modify(|data| {
+            let group = find_group(data, id)?;
+            group.accounts.retain(|e| !accounts.contains(e));
+
+            Ok(group.clone())
+        })
+        .await
+    }
+
+    pub async fn add_watch_only_account(
+        &self,
+        account: WatchOnlyAccount,
+    ) -> Result<WatchOnlyAccount, String> {
+        self.modify(|data| {
+            if data
+                .watch_only_accounts
+                .iter()
+                .any(|e| e.address == account.address)
+            {
+                return Err("Watch-only account already exists").handle_error();
+            }
+
+            data.watch_only_accounts.push(account.clone());
+
+            Ok(account)
+        })
+        .await
+    }
+
+    pub async fn rename_watch_only_account(
+        &self,
+        address: &str,
+        name: String,
+    ) -> Result<WatchOnlyAccount, String> {
+        self.modify(|data| {
+            let account = data
+                .watch_only_accounts
+                .iter_mut()
+                .find(|e| e.address == address)
+                .ok_or(WATCH_ONLY_ACCOUNT_NOT_FOUND_ERROR)
+                .handle_error()?;
+            account.name = name;
+
+            Ok(account.clone())
+        })
+        .await
+    }
+
+    pub async fn take_watch_only_accounts(
+        &self,
+        addresses: &[String],
+    ) -> Result<Vec<WatchOnlyAccount>, String> {
+        if !self
+            .data
+            .read()
+            .await
+            .watch_only_accounts
+            .iter()
+            .any(|e| addresses.contains(&e.address))
+        {
+            return Ok(Vec::new());
+        }
+
+        self.modify(|data| {
+            let (taken, remaining) = data
+                .watch_only_accounts
+                .drain(..)
+                .partition::<Vec<_>, _>(|e| addresses.contains(&e.address));
+
+            data.watch_only_accounts = remaining;
+
+            Ok(taken)
+        })
+        .await
+    }
+
+    pub async fn remove_watch_only_account(
+        &self,
+        address: &str,
+    ) -> Result<Option<WatchOnlyAccount>, String> {
+        self.modify(|data| {
+            let position = match data
+                .watch_only_accounts
+                .iter()
+                .position(|e| e.address == address)
+            {
+                Some(position) => position,
+                None => return Ok(None),
+            };
+
+            let account = data.watch_only_accounts.remove(position);
+
+            remove_accounts(data, &[account.address.clone()]);
+
+            Ok(Some(account))
+        })
+        .await
+    }
+
+    pub async fn clear(&self) -> Result<(), String> {
+        let mut data = self.data.write().await;
+
+        self.storage.remove(STORAGE_KEY).await.handle_error()?;
+
+        *data = AccountsMetadata::default();
+
+        Ok(())
+    }
+
+    pub async fn reload(&self) -> Result<(), String> {
+        let mut data = self.data.write().await;
+
+        *data = load_metadata(self.storage.as_ref()).await?;
+
+        Ok(())
+    }
+
+    // Changes are applied to a copy which replaces the state only after it's saved,
+    // so a failed save leaves memory matching the storage
+    async fn modify<F, T>(&self, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut AccountsMetadata) -> Result<T, String>,
+    {
+        let mut data = self.data.write().await;
+
+        let mut updated = data.clone();
+
+        let result = f(&mut updated)?;
+
+        self.save(&updated).await?;
+
+        *data = updated;
+
+        Ok(result)
+    }
+
+    async fn save(&self, data: &AccountsMetadata) -> Result<(), String> {
+        let data = serde_json::to_string(&AccountsMetadata {
+            version: CURRENT_VERSION,
+            ..data.clone()
+        })
+        .handle_error()?;
+
+        self.storage.set(STORAGE_KEY, &data).await.handle_error()
+    }
+}
+
+fn remove_accounts(data: &mut AccountsMetadata, accounts: &[String]) {
+    for account in accounts {
+        data.accounts.remove(account);
+    }
+    data.order.retain(|e| !accounts.contains(e));
+
+    for group in &mut data.groups {
+        group.accounts.retain(|e| !accounts.contains(e));
+    }
+}
+
+fn find_group<'a>(
+    data: &'a mut AccountsMetadata,
+    id: &str,
+) -> Result<&'a mut AccountGroup, String> {
+    data.groups
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or(GROUP_NOT_FOUND_ERROR)
+        .handle_error()
+}
+
+fn check_parent(
+    data: &AccountsMetadata,
+    id: Option<&str>,
+    parent_id: Option<&str>,
+) -> Result<(), String> {
+    let parent_id = match parent_id {
+        Some(parent_id) => parent_id,
+        None => return Ok(()),
+    };
+
+    if id == Some(parent_id) {
+        return Err("Group can't be nested into itself").handle_error();
+    }
+
+    let parent = data
+        .groups
+        .iter()
+        .find(|e| e.id == parent_id)
+        .ok_or("Parent group not found")
+        .handle_error()?;
+
+    if parent.parent_id.is_some() {
+        return Err("Groups can be nested only one level deep").handle_error();
+    }
+
+    if let Some(id) = id {
+        if data
+            .groups
+            .iter()
+            .any(|e| e.parent_id.as_deref() == Some(id))
+        {
+            return Err("Group with nested groups can't be nested").handle_error();
+        }
+    }
+
+    Ok(())
+}
+
+async fn load_metadata(storage: &dyn Storage) -> Result<AccountsMetadata, String> {
+    let data = match storage.get(STORAGE_KEY).await.handle_error()? {
+        Some(data) => serde_json::from_str::<AccountsMetadata>(&data).handle_error()?,
+        None => AccountsMetadata::default(),
+    };
+
+    if data.version > CURRENT_VERSION {
+        return Err("Unsupported accounts metadata version").handle_error();
+    }
+
+    Ok(data)
+}
@@ -1,6 +1,9 @@
+mod handler;
+mod metadata;
 mod models;
 
 use std::{
+    ops::Deref,
     os::raw::{c_char, c_longlong, c_void},
     sync::Arc,
 };
@@ -9,26 +12,48 @@ use allo_isolate::Isolate;
 use nekoton::{core::accounts_storage::AccountsStorage, external::Storage};
 
 use crate::{
-    core::accounts_storage::models::{AccountToAddHelper, AssetsListHelper},
+    core::accounts_storage::{
+        handler::AccountsStorageHandlerImpl,
+        metadata::AccountsMetadataStorage,
+        models::{
+            AccountEntry, AccountKind, AccountMetadata, AccountMetadataChange, AccountToAddHelper,
+            AccountsStorageEvent, AssetsListHelper, TokenWalletChange, WatchOnlyAccount,
+        },
+    },
     external::storage::StorageImpl,
-    parse_address, runtime, HandleError, MatchResult, PostWithResult, ToPtrAddress,
-    ToStringFromPtr, RUNTIME,
+    parse_address, runtime, HandleError, MatchResult, PostWithResult, ToOptionalStringFromPtr,
+    ToPtrAddress, ToStringFromPtr, RUNTIME,
 };
 
 #[no_mangle]
-pub unsafe extern "C" fn nt_accounts_storage_create(result_port: c_longlong, storage: *mut c_void) {
+pub unsafe extern "C" fn nt_accounts_storage_create(
+    result_port: c_longlong,
+    on_changed_port: c_longlong,
+    storage: *mut c_void,
+) {
     let storage = (&*(storage as *mut Arc<StorageImpl>)).clone();
 
     runtime!().spawn(async move {
-        async fn internal_fn(storage: Arc<dyn Storage>) -> Result<serde_json::Value, String> {
+        async fn internal_fn(
+            on_changed_port: i64,
+            storage: Arc<dyn Storage>,
+        ) -> Result<serde_json::Value, String> {
+            let metadata = AccountsMetadataStorage::load(storage.clone()).await?;
+
             let accounts_storage = AccountsStorage::load(storage).await.handle_error()?;
 
-            let ptr = Box::into_raw(Box::new(accounts_storage));
+            let handler = AccountsStorageHandlerImpl::new(on_changed_port);
+
+            let ptr = Box::into_raw(Box::new(AccountsStorageImpl {
+                accounts_storage,
+                metadata,
+                handler,
+            }));
 
             serde_json::to_value(ptr.to_ptr_address()).handle_error()
         }
 
-        let result = internal_fn(storage).await.match_result();
+        let result = internal_fn(on_changed_port, storage).await.match_result();
 
         Isolate::new(result_port)
             .post_with_result(result.to_ptr_address())
@@ -41,7 +66,7 @@ pub unsafe extern "C" fn nt_accounts_storage_entries(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     runtime!().spawn(async move {
         async fn internal_fn(
@@ -74,13 +99,13 @@ pub unsafe extern "C" fn nt_accounts_storage_add_account(
     accounts_storage: *mut c_void,
     new_account: *mut c_char,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     let new_account = new_account.to_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
             new_account: String,
         ) -> Result<serde_json::Value, String> {
             let new_account = serde_json::from_str::<AccountToAddHelper>(&new_account)
@@ -92,7 +117,21 @@ pub unsafe extern "C" fn nt_accounts_storage_add_account(
                 .await
                 .handle_error()?;
 
-            serde_json::to_value(&AssetsListHelper(entry)).handle_error()
+            let entry = AssetsListHelper(entry);
+
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            let entries = vec![entry];
+
+            accounts_storage
+                .promote_watch_only_accounts(&entries)
+                .await?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::AccountsAdded(entries));
+
+            Ok(value)
         }
 
         let result = internal_fn(accounts_storage, new_account)
@@ -111,13 +150,13 @@ pub unsafe extern "C" fn nt_accounts_storage_add_accounts(
     accounts_storage: *mut c_void,
     new_accounts: *mut c_char,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     let new_accounts = new_accounts.to_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
             new_accounts: String,
         ) -> Result<serde_json::Value, String> {
             let new_accounts = serde_json::from_str::<Vec<AccountToAddHelper>>(&new_accounts)
@@ -134,7 +173,17 @@ pub unsafe extern "C" fn nt_accounts_storage_add_accounts(
                 .map(AssetsListHelper)
                 .collect::<Vec<_>>();
 
-            serde_json::to_value(&entries).handle_error()
+            let value = serde_json::to_value(&entries).handle_error()?;
+
+            accounts_storage
+                .promote_watch_only_accounts(&entries)
+                .await?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::AccountsAdded(entries));
+
+            Ok(value)
         }
 
         let result = internal_fn(accounts_storage, new_accounts)
@@ -154,14 +203,14 @@ pub unsafe extern "C" fn nt_accounts_storage_rename_account(
     account: *mut c_char,
     name: *mut c_char,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     let account = account.to_string_from_ptr();
     let name = name.to_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
             account: String,
             name: String,
         ) -> Result<serde_json::Value, String> {
@@ -170,7 +219,15 @@ pub unsafe extern "C" fn nt_accounts_storage_rename_account(
                 .await
                 .handle_error()?;
 
-            serde_json::to_value(&AssetsListHelper(entry)).handle_error()
+            let entry = AssetsListHelper(entry);
+
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::AccountRenamed(entry));
+
+            Ok(value)
         }
 
         let result = internal_fn(accounts_storage, account, name)
@@ -191,7 +248,7 @@ pub unsafe extern "C" fn nt_accounts_storage_add_token_wallet(
     network_group: *mut c_char,
     root_token_contract: *mut c_char,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     let account = account.to_string_from_ptr();
     let network_group = network_group.to_string_from_ptr();
@@ -199,7 +256,7 @@ pub unsafe extern "C" fn nt_accounts_storage_add_token_wallet(
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
             account: String,
             network_group: String,
             root_token_contract: String,
@@ -207,11 +264,23 @@ pub unsafe extern "C" fn nt_accounts_storage_add_token_wallet(
             let root_token_contract = parse_address(&root_token_contract)?;
 
             let entry = accounts_storage
-                .add_token_wallet(&account, &network_group, root_token_contract)
+                .add_token_wallet(&account, &network_group, root_token_contract.clone())
                 .await
                 .handle_error()?;
 
-            serde_json::to_value(&AssetsListHelper(entry)).handle_error()
+            let entry = AssetsListHelper(entry);
+
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::TokenWalletAdded(TokenWalletChange {
+                    account: entry,
+                    network_group,
+                    root_token_contract,
+                }));
+
+            Ok(value)
         }
 
         let result = internal_fn(
@@ -237,7 +306,7 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_token_wallet(
     network_group: *mut c_char,
     root_token_contract: *mut c_char,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     let account = account.to_string_from_ptr();
     let network_group = network_group.to_string_from_ptr();
@@ -245,7 +314,7 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_token_wallet(
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
             account: String,
             network_group: String,
             root_token_contract: String,
@@ -257,7 +326,21 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_token_wallet(
                 .await
                 .handle_error()?;
 
-            serde_json::to_value(&AssetsListHelper(entry)).handle_error()
+            let entry = AssetsListHelper(entry);
+
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::TokenWalletRemoved(
+                    TokenWalletChange {
+                        account: entry,
+                        network_group,
+                        root_token_contract,
+                    },
+                ));
+
+            Ok(value)
         }
 
         let result = internal_fn(
@@ -281,13 +364,13 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_account(
     accounts_storage: *mut c_void,
     account: *mut c_char,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     let account = account.to_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
             account: String,
         ) -> Result<serde_json::Value, String> {
             let entry = accounts_storage
@@ -296,7 +379,20 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_account(
                 .handle_error()?
                 .map(AssetsListHelper);
 
-            serde_json::to_value(entry).handle_error()
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            if let Some(entry) = entry {
+                accounts_storage
+                    .metadata
+                    .remove_accounts(&[account])
+                    .await?;
+
+                accounts_storage
+                    .handler
+                    .on_changed(AccountsStorageEvent::AccountsRemoved(vec![entry]));
+            }
+
+            Ok(value)
         }
 
         let result = internal_fn(accounts_storage, account).await.match_result();
@@ -313,13 +409,13 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_accounts(
     accounts_storage: *mut c_void,
     accounts: *mut c_char,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     let accounts = accounts.to_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
             accounts: String,
         ) -> Result<serde_json::Value, String> {
             let accounts = serde_json::from_str::<Vec<&str>>(&accounts).handle_error()?;
@@ -332,9 +428,173 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_accounts(
                 .map(AssetsListHelper)
                 .collect::<Vec<_>>();
 
+            let value = serde_json::to_value(&entries).handle_error()?;
+
+            let accounts = entries
+                .iter()
+                .map(|e| e.0.ton_wallet.address.to_string())
+                .collect::<Vec<_>>();
+
+            accounts_storage.metadata.remove_accounts(&accounts).await?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::AccountsRemoved(entries));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, accounts).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_entries_with_metadata(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+        ) -> Result<serde_json::Value, String> {
+            let metadata = accounts_storage.metadata.get().await;
+
+            let regular_accounts = accounts_storage
+                .stored_data()
+                .await
+                .accounts()
+                .values()
+                .cloned()
+                .map(|e| AccountKind::Regular(AssetsListHelper(e)))
+                .collect::<Vec<_>>();
+
+            let watch_only_accounts = metadata
+                .watch_only_accounts
+                .iter()
+                .cloned()
+                .map(AccountKind::WatchOnly);
+
+            let mut entries = regular_accounts
+                .into_iter()
+                .chain(watch_only_accounts)
+                .map(|account| AccountEntry {
+                    metadata: metadata
+                        .accounts
+                        .get(&account.address())
+                        .cloned()
+                        .unwrap_or_default(),
+                    account,
+                })
+                .collect::<Vec<_>>();
+
+            entries.sort_by_cached_key(|e| {
+                let address = e.account.address();
+
+                let position = metadata
+                    .order
+                    .iter()
+                    .position(|e| e == &address)
+                    .unwrap_or(usize::MAX);
+
+                (position, e.account.name().to_owned(), address)
+            });
+
             serde_json::to_value(&entries).handle_error()
         }
 
+        let result = internal_fn(accounts_storage).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_update_account_metadata(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    account: *mut c_char,
+    metadata: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let account = account.to_string_from_ptr();
+    let metadata = metadata.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            account: String,
+            metadata: String,
+        ) -> Result<serde_json::Value, String> {
+            let metadata = serde_json::from_str::<AccountMetadata>(&metadata).handle_error()?;
+
+            accounts_storage
+                .check_accounts(std::slice::from_ref(&account))
+                .await?;
+
+            let metadata = accounts_storage
+                .metadata
+                .update_account(account.clone(), metadata)
+                .await?;
+
+            let value = serde_json::to_value(&metadata).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::AccountMetadataUpdated(
+                    AccountMetadataChange { account, metadata },
+                ));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, account, metadata)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_reorder_accounts(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    accounts: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let accounts = accounts.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            accounts: String,
+        ) -> Result<serde_json::Value, String> {
+            let accounts = serde_json::from_str::<Vec<String>>(&accounts).handle_error()?;
+
+            accounts_storage
+                .metadata
+                .reorder_accounts(accounts.clone())
+                .await?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::AccountsReordered(accounts));
+
+            Ok(serde_json::Value::Null)
+        }
+
         let result = internal_fn(accounts_storage, accounts).await.match_result();
 
         Isolate::new(result_port)
@@ -343,19 +603,464 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_accounts(
     });
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_groups(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+        ) -> Result<serde_json::Value, String> {
+            let groups = accounts_storage.metadata.get().await.groups;
+
+            serde_json::to_value(&groups).handle_error()
+        }
+
+        let result = internal_fn(accounts_storage).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_create_group(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    name: *mut c_char,
+    parent_id: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let name = name.to_string_from_ptr();
+    let parent_id = parent_id.to_optional_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            name: String,
+            parent_id: Option<String>,
+        ) -> Result<serde_json::Value, String> {
+            let group = accounts_storage
+                .metadata
+                .create_group(name, parent_id)
+                .await?;
+
+            let value = serde_json::to_value(&group).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::GroupCreated(group));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, name, parent_id)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_rename_group(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    group: *mut c_char,
+    name: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let group = group.to_string_from_ptr();
+    let name = name.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            group: String,
+            name: String,
+        ) -> Result<serde_json::Value, String> {
+            let group = accounts_storage.metadata.rename_group(&group, name).await?;
+
+            let value = serde_json::to_value(&group).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::GroupsUpdated(vec![group]));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, group, name)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_move_group(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    group: *mut c_char,
+    parent_id: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let group = group.to_string_from_ptr();
+    let parent_id = parent_id.to_optional_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            group: String,
+            parent_id: Option<String>,
+        ) -> Result<serde_json::Value, String> {
+            let group = accounts_storage
+                .metadata
+                .move_group(&group, parent_id)
+                .await?;
+
+            let value = serde_json::to_value(&group).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::GroupsUpdated(vec![group]));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, group, parent_id)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_remove_group(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    group: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let group = group.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            group: String,
+        ) -> Result<serde_json::Value, String> {
+            let (group, children) = accounts_storage.metadata.remove_group(&group).await?;
+
+            let value = serde_json::to_value(&group).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::GroupRemoved(group));
+
+            if !children.is_empty() {
+                accounts_storage
+                    .handler
+                    .on_changed(AccountsStorageEvent::GroupsUpdated(children));
+            }
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, group).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_add_accounts_to_group(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    group: *mut c_char,
+    accounts: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let group = group.to_string_from_ptr();
+    let accounts = accounts.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            group: String,
+            accounts: String,
+        ) -> Result<serde_json::Value, String> {
+            let accounts = serde_json::from_str::<Vec<String>>(&accounts).handle_error()?;
+
+            accounts_storage.check_accounts(&accounts).await?;
+
+            let groups = accounts_storage
+                .metadata
+                .add_accounts_to_group(&group, accounts)
+                .await?;
+
+            let value = serde_json::to_value(&groups).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::GroupsUpdated(groups));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, group, accounts)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_remove_accounts_from_group(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    group: *mut c_char,
+    accounts: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let group = group.to_string_from_ptr();
+    let accounts = accounts.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            group: String,
+            accounts: String,
+        ) -> Result<serde_json::Value, String> {
+            let accounts = serde_json::from_str::<Vec<String>>(&accounts).handle_error()?;
+
+            let group = accounts_storage
+                .metadata
+                .remove_accounts_from_group(&group, accounts)
+                .await?;
+
+            let value = serde_json::to_value(&group).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::GroupsUpdated(vec![group]));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, group, accounts)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_watch_only_accounts(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+        ) -> Result<serde_json::Value, String> {
+            let watch_only_accounts = accounts_storage.metadata.get().await.watch_only_accounts;
+
+            serde_json::to_value(&watch_only_accounts).handle_error()
+        }
+
+        let result = internal_fn(accounts_storage).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_add_watch_only_account(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    new_account: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let new_account = new_account.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            new_account: String,
+        ) -> Result<serde_json::Value, String> {
+            let mut new_account =
+                serde_json::from_str::<WatchOnlyAccount>(&new_account).handle_error()?;
+
+            new_account.address = parse_address(&new_account.address)?.to_string();
+
+            if let Some(abi) = &new_account.abi {
+                ton_abi::Contract::load(abi.as_str()).handle_error()?;
+            }
+
+            if accounts_storage
+                .stored_data()
+                .await
+                .accounts()
+                .contains_key(&new_account.address)
+            {
+                return Err("Account already exists").handle_error();
+            }
+
+            let entry = accounts_storage
+                .metadata
+                .add_watch_only_account(new_account)
+                .await?;
+
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::WatchOnlyAccountAdded(entry));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, new_account)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_rename_watch_only_account(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    account: *mut c_char,
+    name: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let account = account.to_string_from_ptr();
+    let name = name.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            account: String,
+            name: String,
+        ) -> Result<serde_json::Value, String> {
+            let entry = accounts_storage
+                .metadata
+                .rename_watch_only_account(&account, name)
+                .await?;
+
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::WatchOnlyAccountUpdated(entry));
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, account, name)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_accounts_storage_remove_watch_only_account(
+    result_port: c_longlong,
+    accounts_storage: *mut c_void,
+    account: *mut c_char,
+) {
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
+
+    let account = account.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            accounts_storage: &AccountsStorageImpl,
+            account: String,
+        ) -> Result<serde_json::Value, String> {
+            let entry = accounts_storage
+                .metadata
+                .remove_watch_only_account(&account)
+                .await?;
+
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            if let Some(entry) = entry {
+                accounts_storage
+                    .handler
+                    .on_changed(AccountsStorageEvent::WatchOnlyAccountRemoved(entry));
+            }
+
+            Ok(value)
+        }
+
+        let result = internal_fn(accounts_storage, account).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn nt_accounts_storage_clear(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
         ) -> Result<serde_json::Value, String> {
             accounts_storage.clear().await.handle_error()?;
 
+            accounts_storage.metadata.clear().await?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::Cleared);
+
             Ok(serde_json::Value::Null)
         }
 
@@ -372,14 +1077,20 @@ pub unsafe extern "C" fn nt_accounts_storage_reload(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
 ) {
-    let accounts_storage = &*(accounts_storage as *mut AccountsStorage);
+    let accounts_storage = &*(accounts_storage as *mut AccountsStorageImpl);
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            accounts_storage: &AccountsStorage,
+            accounts_storage: &AccountsStorageImpl,
         ) -> Result<serde_json::Value, String> {
             accounts_storage.reload().await.handle_error()?;
 
+            accounts_storage.metadata.reload().await?;
+
+            accounts_storage
+                .handler
+                .on_changed(AccountsStorageEvent::Reloaded);
+
             Ok(serde_json::Value::Null)
         }
 
@@ -407,5 +1118,59 @@ pub unsafe extern "C" fn nt_accounts_storage_verify_data(data: *mut c_char) -> *
 #[no_mangle]
 pub unsafe extern "C" fn nt_accounts_storage_free_ptr(ptr: *mut c_void) {
     println!("nt_accounts_storage_free_ptr");
-    Box::from_raw(ptr as *mut AccountsStorage);
+    Box::from_raw(ptr as *mut AccountsStorageImpl);
+}
+
+pub(crate) struct AccountsStorageImpl {
+    accounts_storage: AccountsStorage,
+    metadata: AccountsMetadataStorage,
+    handler: AccountsStorageHandlerImpl,
+}
+
+impl AccountsStorageImpl {
+    // Metadata can be attached only to stored or watch-only accounts
+    async fn check_accounts(&self, accounts: &[String]) -> Result<(), String> {
+        let watch_only_accounts = self.metadata.get().await.watch_only_accounts;
+        let stored_data = self.stored_data().await;
+
+        let is_known = |account: &String| {
+            watch_only_accounts.iter().any(|e| &e.address == account)
+                || stored_data.accounts().contains_key(account)
+        };
+
+        if !accounts.iter().all(is_known) {
+            return Err("Account not found").handle_error();
+        }
+
+        Ok(())
+    }
+
+    // Adding a regular account for a watched address replaces the watch-only entry
+    // but keeps its metadata, ordering and group membership
+    async fn promote_watch_only_accounts(
+        &self,
+        entries: &[AssetsListHelper],
+    ) -> Result<(), String> {
+        let addresses = entries
+            .iter()
+            .map(|e| e.0.ton_wallet.address.to_string())
+            .collect::<Vec<_>>();
+
+        let accounts = self.metadata.take_watch_only_accounts(&addresses).await?;
+
+        for account in accounts {
+            self.handler
+                .on_changed(AccountsStorageEvent::WatchOnlyAccountRemoved(account));
+        }
+
+        Ok(())
+    }
+}
+
+impl Deref for AccountsStorageImpl {
+    type Target = AccountsStorage;
+
+    fn deref(&self) -> &Self::Target {
+        &self.accounts_storage
+    }
 }
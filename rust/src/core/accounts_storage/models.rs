@@ -8,7 +8,7 @@ use nekoton_utils::{serde_address, serde_optional_address, serde_public_key};
 use serde::{Deserialize, Serialize};
 use ton_block::MsgAddressInt;
 
-use crate::core::ton_wallet::models::WalletTypeDef;
+use crate::core::ton_wallet::models::{WalletTypeDef, WalletTypeHelper};
 
 #[derive(Deserialize)]
 pub struct AccountToAddHelper(#[serde(with = "AccountToAddDef")] pub AccountToAdd);
@@ -51,3 +51,109 @@ pub struct TonWalletAssetDef {
     #[serde(with = "WalletTypeDef")]
     pub contract: WalletType,
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum AccountsStorageEvent {
+    AccountsAdded(Vec<AssetsListHelper>),
+    AccountRenamed(AssetsListHelper),
+    TokenWalletAdded(TokenWalletChange),
+    TokenWalletRemoved(TokenWalletChange),
+    AccountsRemoved(Vec<AssetsListHelper>),
+    AccountMetadataUpdated(AccountMetadataChange),
+    AccountsReordered(Vec<String>),
+    GroupCreated(AccountGroup),
+    GroupsUpdated(Vec<AccountGroup>),
+    GroupRemoved(AccountGroup),
+    WatchOnlyAccountAdded(WatchOnlyAccount),
+    WatchOnlyAccountUpdated(WatchOnlyAccount),
+    WatchOnlyAccountRemoved(WatchOnlyAccount),
+    Cleared,
+    Reloaded,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenWalletChange {
+    pub account: AssetsListHelper,
+    pub network_group: NetworkGroup,
+    #[serde(with = "serde_address")]
+    pub root_token_contract: MsgAddressInt,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountMetadataChange {
+    pub account: String,
+    pub metadata: AccountMetadata,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AccountsMetadata {
+    pub version: u32,
+    pub accounts: HashMap<String, AccountMetadata>,
+    pub order: Vec<String>,
+    pub groups: Vec<AccountGroup>,
+    pub watch_only_accounts: Vec<WatchOnlyAccount>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AccountMetadata {
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+    pub is_hidden: bool,
+    pub is_archived: bool,
+    pub hidden_networks: Vec<NetworkGroup>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountEntry {
+    pub account: AccountKind,
+    pub metadata: AccountMetadata,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum AccountKind {
+    Regular(AssetsListHelper),
+    WatchOnly(WatchOnlyAccount),
+}
+
+impl AccountKind {
+    pub fn address(&self) -> String {
+        match self {
+            AccountKind::Regular(assets_list) => assets_list.0.ton_wallet.address.to_string(),
+            AccountKind::WatchOnly(account) => account.address.clone(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            AccountKind::Regular(assets_list) => &assets_list.0.name,
+            AccountKind::WatchOnly(account) => &account.name,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountGroup {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub accounts: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchOnlyAccount {
+    pub address: String,
+    pub name: String,
+    pub wallet_type: Option<WalletTypeHelper>,
+    pub abi: Option<String>,
+}
@@ -0,0 +1,203 @@
+mod models;
+mod storage;
+
+use std::{
+    os::raw::{c_char, c_longlong, c_void},
+    sync::Arc,
+};
+
+use allo_isolate::Isolate;
+use nekoton::external::Storage;
+
+use crate::{
+    core::address_book::{
+        models::{Contact, ContactToAdd},
+        storage::AddressBook,
+    },
+    external::storage::StorageImpl,
+    runtime, HandleError, MatchResult, PostWithResult, ToPtrAddress, ToStringFromPtr, RUNTIME,
+};
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_address_book_create(result_port: c_longlong, storage: *mut c_void) {
+    let storage = (&*(storage as *mut Arc<StorageImpl>)).clone();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(storage: Arc<dyn Storage>) -> Result<serde_json::Value, String> {
+            let address_book = AddressBook::load(storage).await?;
+
+            let ptr = Box::into_raw(Box::new(address_book));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        let result = internal_fn(storage).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_address_book_entries(
+    result_port: c_longlong,
+    address_book: *mut c_void,
+) {
+    let address_book = &*(address_book as *mut AddressBook);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(address_book: &AddressBook) -> Result<serde_json::Value, String> {
+            let contacts = address_book.contacts().await;
+
+            serde_json::to_value(&contacts).handle_error()
+        }
+
+        let result = internal_fn(address_book).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_address_book_find_by_address(
+    result_port: c_longlong,
+    address_book: *mut c_void,
+    address: *mut c_char,
+) {
+    let address_book = &*(address_book as *mut AddressBook);
+
+    let address = address.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            address_book: &AddressBook,
+            address: String,
+        ) -> Result<serde_json::Value, String> {
+            let contacts = address_book.find_by_address(&address).await?;
+
+            serde_json::to_value(&contacts).handle_error()
+        }
+
+        let result = internal_fn(address_book, address).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_address_book_add_contact(
+    result_port: c_longlong,
+    address_book: *mut c_void,
+    contact: *mut c_char,
+) {
+    let address_book = &*(address_book as *mut AddressBook);
+
+    let contact = contact.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            address_book: &AddressBook,
+            contact: String,
+        ) -> Result<serde_json::Value, String> {
+            let contact = serde_json::from_str::<ContactToAdd>(&contact).handle_error()?;
+
+            let contact = address_book.add_contact(contact).await?;
+
+            serde_json::to_value(&contact).handle_error()
+        }
+
+        let result = internal_fn(address_book, contact).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_address_book_update_contact(
+    result_port: c_longlong,
+    address_book: *mut c_void,
+    contact: *mut c_char,
+) {
+    let address_book = &*(address_book as *mut AddressBook);
+
+    let contact = contact.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            address_book: &AddressBook,
+            contact: String,
+        ) -> Result<serde_json::Value, String> {
+            let contact = serde_json::from_str::<Contact>(&contact).handle_error()?;
+
+            let contact = address_book.update_contact(contact).await?;
+
+            serde_json::to_value(&contact).handle_error()
+        }
+
+        let result = internal_fn(address_book, contact).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_address_book_remove_contact(
+    result_port: c_longlong,
+    address_book: *mut c_void,
+    id: *mut c_char,
+) {
+    let address_book = &*(address_book as *mut AddressBook);
+
+    let id = id.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            address_book: &AddressBook,
+            id: String,
+        ) -> Result<serde_json::Value, String> {
+            let contact = address_book.remove_contact(&id).await?;
+
+            serde_json::to_value(&contact).handle_error()
+        }
+
+        let result = internal_fn(address_book, id).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_address_book_clear(result_port: c_longlong, address_book: *mut c_void) {
+    let address_book = &*(address_book as *mut AddressBook);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(address_book: &AddressBook) -> Result<serde_json::Value, String> {
+            address_book.clear().await?;
+
+            Ok(serde_json::Value::Null)
+        }
+
+        let result = internal_fn(address_book).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_address_book_free_ptr(ptr: *mut c_void) {
+    println!("nt_address_book_free_ptr");
+    Box::from_raw(ptr as *mut AddressBook);
+}
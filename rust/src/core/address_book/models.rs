@@ -0,0 +1,34 @@
+use nekoton::core::accounts_storage::NetworkGroup;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AddressBookData {
+    pub version: u32,
+    pub contacts: Vec<Contact>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contact {
+    pub id: String,
+    pub name: String,
+    pub addresses: Vec<ContactAddress>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactToAdd {
+    pub name: String,
+    pub addresses: Vec<ContactAddress>,
+    pub notes: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactAddress {
+    pub address: String,
+    pub label: Option<String>,
+    pub networks: Vec<NetworkGroup>,
+}
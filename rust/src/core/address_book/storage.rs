@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use nekoton::external::Storage;
+use tokio::sync::RwLock;
+
+use crate::{
+    core::address_book::models::{AddressBookData, Contact, ContactAddress, ContactToAdd},
+    HandleError,
+};
+
+const STORAGE_KEY: &str = "__nekoton_flutter__address_book";
+const CURRENT_VERSION: u32 = 1;
+
+pub struct AddressBook {
+    storage: Arc<dyn Storage>,
+    data: RwLock<AddressBookData>,
+}
+
+impl AddressBook {
+    pub async fn load(storage: Arc<dyn Storage>) -> Result<Self, String> {
+        let data = load_data(storage.as_ref()).await?;
+
+        Ok(Self {
+            storage,
+            data: RwLock::new(data),
+        })
+    }
+
+    pub async fn contacts(&self) -> Vec<Contact> {
+        self.data.read().await.contacts.clone()
+    }
+
+    pub async fn find_by_address(&self, address: &str) -> Result<Vec<Contact>, String> {
+        let address = normalize_address(address)?;
+
+        let contacts = self
+            .data
+            .read()
+            .await
+            .contacts
+            .iter()
+            .filter(|e| e.addresses.iter().any(|e| e.address == address))
+            .cloned()
+            .collect();
+
+        Ok(contacts)
+    }
+
+    pub async fn add_contact(&self, contact: ContactToAdd) -> Result<Contact, String> {
+        let contact = Contact {
+            id: hex::encode(rand::random::<[u8; 8]>()),
+            name: contact.name,
+            addresses: normalize_addresses(contact.addresses)?,
+            notes: contact.notes,
+        };
+
+        self.modify(|data| {
+            data.contacts.push(contact.clone());
+
+            Ok(contact)
+        })
+        .await
+    }
+
+    pub async fn update_contact(&self, contact: Contact) -> Result<Contact, String> {
+        let contact = Contact {
+            addresses: normalize_addresses(contact.addresses)?,
+            ..contact
+        };
+
+        self.modify(|data| {
+            let entry = data
+                .contacts
+                .iter_mut()
+                .find(|e| e.id == contact.id)
+                .ok_or("Contact not found")
+                .handle_error()?;
+
+            *entry = contact.clone();
+
+            Ok(contact)
+        })
+        .await
+    }
+
+    pub async fn remove_contact(&self, id: &str) -> Result<Option<Contact>, String> {
+        self.modify(|data| {
+            let position = match data.contacts.iter().position(|e| e.id == id) {
+                Some(position) => position,
+                None => return Ok(None),
+            };
+
+            Ok(Some(data.contacts.remove(position)))
+        })
+        .await
+    }
+
+    pub async fn clear(&self) -> Result<(), String> {
+        let mut data = self.data.write().await;
+
+        self.storage.remove(STORAGE_KEY).await.handle_error()?;
+
+        *data = AddressBookData::default();
+
+        Ok(())
+    }
+
+    // Changes are applied to a copy which replaces the state only after it's saved
+    async fn modify<F, T>(&self, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut AddressBookData) -> Result<T, String>,
+    {
+        let mut data = self.data.write().await;
+
+        let mut updated = data.clone();
+
+        let result = f(&mut updated)?;
+
+        self.save(&updated).await?;
+
+        *data = updated;
+
+        Ok(result)
+    }
+
+    async fn save(&self, data: &AddressBookData) -> Result<(), String> {
+        let data = serde_json::to_string(&AddressBookData {
+            version: CURRENT_VERSION,
+            ..data.clone()
+        })
+        .handle_error()?;
+
+        self.storage.set(STORAGE_KEY, &data).await.handle_error()
+    }
+}
+
+fn normalize_addresses(addresses: Vec<ContactAddress>) -> Result<Vec<ContactAddress>, String> {
+    addresses
+        .into_iter()
+        .map(|e| {
+            Ok(ContactAddress {
+                address: normalize_address(&e.address)?,
+                ..e
+            })
+        })
+        .collect()
+}
+
+fn normalize_address(address: &str) -> Result<String, String> {
+    if !nekoton_utils::validate_address(address) {
+        return Err("Invalid address").handle_error();
+    }
+
+    let address = nekoton_utils::repack_address(address)
+        .handle_error()?
+        .to_string();
+
+    Ok(address)
+}
+
+async fn load_data(storage: &dyn Storage) -> Result<AddressBookData, String> {
+    let data = match storage.get(STORAGE_KEY).await.handle_error()? {
+        Some(data) => serde_json::from_str::<AddressBookData>(&data).handle_error()?,
+        None => AddressBookData::default(),
+    };
+
+    if data.version > CURRENT_VERSION {
+        return Err("Unsupported address book version").handle_error();
+    }
+
+    Ok(data)
+}
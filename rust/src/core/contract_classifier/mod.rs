@@ -0,0 +1,177 @@
+mod models;
+
+use std::{
+    collections::HashMap,
+    os::raw::{c_char, c_longlong, c_void},
+    sync::Arc,
+};
+
+use allo_isolate::Isolate;
+use nekoton::{
+    core::{
+        token_wallet::{get_token_root_details, get_token_wallet_details},
+        ton_wallet::extract_wallet_init_data,
+    },
+    transport::{models::RawContractState, Transport},
+};
+use ton_block::MsgAddressInt;
+
+use crate::{
+    clock,
+    core::{
+        contract_classifier::models::{
+            ClassificationConfidence, ContractClassification, ContractKind,
+        },
+        ton_wallet::models::WalletTypeHelper,
+    },
+    parse_address, runtime,
+    transport::match_transport,
+    HandleError, MatchResult, PostWithResult, ToOptionalStringFromPtr, ToPtrAddress,
+    ToStringFromPtr, CLOCK, RUNTIME,
+};
+
+// There is no built-in list of known contracts: `known_contracts` is an
+// optional JSON map of code hash to contract name provided by the caller
+#[no_mangle]
+pub unsafe extern "C" fn nt_classify_contract(
+    result_port: c_longlong,
+    transport: *mut c_void,
+    transport_type: *mut c_char,
+    address: *mut c_char,
+    known_contracts: *mut c_char,
+) {
+    let transport_type = transport_type.to_string_from_ptr();
+    let address = address.to_string_from_ptr();
+    let known_contracts = known_contracts.to_optional_string_from_ptr();
+
+    let transport = match_transport(transport, &transport_type);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            transport: Arc<dyn Transport>,
+            address: String,
+            known_contracts: Option<String>,
+        ) -> Result<serde_json::Value, String> {
+            let address = parse_address(&address)?;
+
+            let known_contracts = known_contracts
+                .as_deref()
+                .map(serde_json::from_str::<HashMap<String, String>>)
+                .transpose()
+                .handle_error()?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(k, v)| (k.trim_start_matches("0x").to_lowercase(), v))
+                .collect::<HashMap<_, _>>();
+
+            let classification =
+                classify_contract(transport.as_ref(), &address, &known_contracts).await?;
+
+            serde_json::to_value(&classification).handle_error()
+        }
+
+        let result = internal_fn(transport, address, known_contracts)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+async fn classify_contract(
+    transport: &dyn Transport,
+    address: &MsgAddressInt,
+    known_contracts: &HashMap<String, String>,
+) -> Result<ContractClassification, String> {
+    let classification = |code_hash, kind, confidence| ContractClassification {
+        address: address.to_string(),
+        code_hash,
+        kind,
+        confidence: Some(confidence),
+    };
+
+    let existing_contract = match transport.get_contract_state(address).await.handle_error()? {
+        RawContractState::Exists(contract) => contract,
+        RawContractState::NotExists => {
+            return Ok(classification(
+                None,
+                ContractKind::NotExists,
+                ClassificationConfidence::High,
+            ))
+        },
+    };
+
+    let code_hash = match &existing_contract.account.storage.state {
+        ton_block::AccountState::AccountActive { state_init } => state_init
+            .code
+            .as_ref()
+            .map(|code| code.repr_hash().to_hex_string()),
+        ton_block::AccountState::AccountUninit => {
+            return Ok(classification(
+                None,
+                ContractKind::Uninit,
+                ClassificationConfidence::High,
+            ))
+        },
+        ton_block::AccountState::AccountFrozen { .. } => {
+            return Ok(classification(
+                None,
+                ContractKind::Frozen,
+                ClassificationConfidence::High,
+            ))
+        },
+    };
+
+    if let Some(name) = code_hash.as_ref().and_then(|e| known_contracts.get(e)) {
+        return Ok(classification(
+            code_hash.clone(),
+            ContractKind::KnownContract { name: name.clone() },
+            ClassificationConfidence::High,
+        ));
+    }
+
+    // Wallet contracts are matched by their exact code hash
+    if let Ok((public_key, wallet_type)) = extract_wallet_init_data(&existing_contract) {
+        return Ok(classification(
+            code_hash,
+            ContractKind::Wallet {
+                public_key: hex::encode(public_key.as_bytes()),
+                wallet_type: WalletTypeHelper(wallet_type),
+            },
+            ClassificationConfidence::High,
+        ));
+    }
+
+    // TIP-3 contracts are matched by the get methods they respond to,
+    // so other contracts implementing the same interface are not excluded
+    if let Ok((details, root_details)) =
+        get_token_wallet_details(clock!().as_ref(), transport, address).await
+    {
+        return Ok(classification(
+            code_hash,
+            ContractKind::TokenWallet {
+                details,
+                root_details,
+            },
+            ClassificationConfidence::Medium,
+        ));
+    }
+
+    if let Ok(details) = get_token_root_details(clock!().as_ref(), transport, address).await {
+        return Ok(classification(
+            code_hash,
+            ContractKind::TokenRoot(details),
+            ClassificationConfidence::Medium,
+        ));
+    }
+
+    // Nothing was matched, so there is no confidence to report
+    Ok(ContractClassification {
+        address: address.to_string(),
+        code_hash,
+        kind: ContractKind::Unknown,
+        confidence: None,
+    })
+}
@@ -0,0 +1,44 @@
+use nekoton::core::models::{RootTokenContractDetails, TokenWalletDetails};
+use serde::Serialize;
+
+use crate::core::ton_wallet::models::WalletTypeHelper;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractClassification {
+    pub address: String,
+    pub code_hash: Option<String>,
+    pub kind: ContractKind,
+    pub confidence: Option<ClassificationConfidence>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum ContractKind {
+    NotExists,
+    Uninit,
+    Frozen,
+    #[serde(rename_all = "camelCase")]
+    Wallet {
+        public_key: String,
+        wallet_type: WalletTypeHelper,
+    },
+    #[serde(rename_all = "camelCase")]
+    TokenWallet {
+        details: TokenWalletDetails,
+        root_details: RootTokenContractDetails,
+    },
+    TokenRoot(RootTokenContractDetails),
+    #[serde(rename_all = "camelCase")]
+    KnownContract {
+        name: String,
+    },
+    Unknown,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClassificationConfidence {
+    High,
+    Medium,
+}
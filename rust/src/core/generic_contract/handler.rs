@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use allo_isolate::Isolate;
 use async_trait::async_trait;
 use nekoton::core::{
@@ -5,9 +7,15 @@ use nekoton::core::{
     models::{ContractState, PendingTransaction, Transaction, TransactionsBatchInfo},
 };
 
-use crate::core::models::{
-    OnMessageExpiredPayload, OnMessageSentPayload, OnStateChangedPayload,
-    OnTransactionsFoundPayload,
+use crate::core::{
+    models::{
+        OnMessageExpiredPayload, OnMessageSentPayload, OnStateChangedPayload,
+        OnTransactionsFoundPayload,
+    },
+    pending_messages::{
+        forget_pending_message, models::RestoredPendingMessages, storage::PendingMessagesStorage,
+    },
+    send_and_wait::waiters::{notify_message_expired, notify_message_sent},
 };
 
 pub struct GenericContractSubscriptionHandlerImpl {
@@ -15,6 +23,7 @@ pub struct GenericContractSubscriptionHandlerImpl {
     on_message_expired_port: Isolate,
     on_state_changed_port: Isolate,
     on_transactions_found_port: Isolate,
+    pending_messages: Option<Arc<PendingMessagesStorage>>,
 }
 
 impl GenericContractSubscriptionHandlerImpl {
@@ -23,12 +32,24 @@ impl GenericContractSubscriptionHandlerImpl {
         on_message_expired_port: i64,
         on_state_changed_port: i64,
         on_transactions_found_port: i64,
+        pending_messages: Option<Arc<PendingMessagesStorage>>,
     ) -> Self {
         Self {
             on_message_sent_port: Isolate::new(on_message_sent_port),
             on_message_expired_port: Isolate::new(on_message_expired_port),
             on_state_changed_port: Isolate::new(on_state_changed_port),
             on_transactions_found_port: Isolate::new(on_transactions_found_port),
+            pending_messages,
+        }
+    }
+
+    pub fn on_pending_messages_restored(&self, restored: RestoredPendingMessages) {
+        for delivered in restored.delivered {
+            self.on_message_sent(delivered.pending_transaction, delivered.transaction);
+        }
+
+        for pending_transaction in restored.expired {
+            self.on_message_expired(pending_transaction);
         }
     }
 }
@@ -40,6 +61,9 @@ impl GenericContractSubscriptionHandler for GenericContractSubscriptionHandlerIm
         pending_transaction: PendingTransaction,
         transaction: Option<Transaction>,
     ) {
+        notify_message_sent(&pending_transaction, &transaction);
+        forget_pending_message(&self.pending_messages, &pending_transaction);
+
         let payload = serde_json::to_string(&OnMessageSentPayload {
             pending_transaction,
             transaction,
@@ -50,6 +74,9 @@ impl GenericContractSubscriptionHandler for GenericContractSubscriptionHandlerIm
     }
 
     fn on_message_expired(&self, pending_transaction: PendingTransaction) {
+        notify_message_expired(&pending_transaction);
+        forget_pending_message(&self.pending_messages, &pending_transaction);
+
         let payload = serde_json::to_string(&OnMessageExpiredPayload {
             pending_transaction,
         })
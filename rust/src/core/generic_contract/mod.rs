@@ -15,9 +15,14 @@ use tokio::sync::RwLock;
 use ton_block::{Block, Deserializable};
 
 use crate::{
-    clock, core::generic_contract::handler::GenericContractSubscriptionHandlerImpl, parse_address,
-    runtime, transport::match_transport, HandleError, MatchResult, PostWithResult, ToPtrAddress,
-    ToStringFromPtr, CLOCK, RUNTIME,
+    clock,
+    core::{
+        generic_contract::handler::GenericContractSubscriptionHandlerImpl,
+        pending_messages::{restore_pending_messages, storage::PendingMessagesStorage},
+    },
+    parse_address, runtime,
+    transport::match_transport,
+    HandleError, MatchResult, PostWithResult, ToPtrAddress, ToStringFromPtr, CLOCK, RUNTIME,
 };
 
 #[no_mangle]
@@ -29,6 +34,7 @@ pub unsafe extern "C" fn nt_generic_contract_subscribe(
     on_transactions_found_port: c_longlong,
     transport: *mut c_void,
     transport_type: *mut c_char,
+    pending_messages: *mut c_void,
     address: *mut c_char,
     preload_transactions: c_uint,
 ) {
@@ -36,6 +42,12 @@ pub unsafe extern "C" fn nt_generic_contract_subscribe(
     let address = address.to_string_from_ptr();
     let preload_transactions = preload_transactions != 0;
 
+    let pending_messages = if !pending_messages.is_null() {
+        Some((&*(pending_messages as *mut Arc<PendingMessagesStorage>)).clone())
+    } else {
+        None
+    };
+
     let transport = match_transport(transport, &transport_type);
 
     runtime!().spawn(async move {
@@ -45,6 +57,7 @@ pub unsafe extern "C" fn nt_generic_contract_subscribe(
             on_state_changed_port: i64,
             on_transactions_found_port: i64,
             transport: Arc<dyn Transport>,
+            pending_messages: Option<Arc<PendingMessagesStorage>>,
             address: String,
             preload_transactions: bool,
         ) -> Result<serde_json::Value, String> {
@@ -55,18 +68,33 @@ pub unsafe extern "C" fn nt_generic_contract_subscribe(
                 on_message_expired_port,
                 on_state_changed_port,
                 on_transactions_found_port,
+                pending_messages.clone(),
             ));
 
-            let generic_contract = GenericContract::subscribe(
+            let mut generic_contract = GenericContract::subscribe(
                 clock!(),
-                transport,
+                transport.clone(),
                 address,
-                handler,
+                handler.clone(),
                 preload_transactions,
             )
             .await
             .handle_error()?;
 
+            // A failed restore keeps the stored messages, it can be retried explicitly
+            // without preventing the subscription from being created
+            if let Some(pending_messages) = pending_messages {
+                if let Ok(restored) = restore_pending_messages(
+                    &mut generic_contract,
+                    &pending_messages,
+                    transport.as_ref(),
+                )
+                .await
+                {
+                    handler.on_pending_messages_restored(restored);
+                }
+            }
+
             let ptr = Box::into_raw(Box::new(Arc::new(RwLock::new(generic_contract))));
 
             serde_json::to_value(ptr.to_ptr_address()).handle_error()
@@ -78,6 +106,7 @@ pub unsafe extern "C" fn nt_generic_contract_subscribe(
             on_state_changed_port,
             on_transactions_found_port,
             transport,
+            pending_messages,
             address,
             preload_transactions,
         )
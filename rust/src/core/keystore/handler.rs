@@ -0,0 +1,23 @@
+use allo_isolate::Isolate;
+
+use crate::core::keystore::models::KeyStoreEvent;
+
+pub struct KeyStoreHandlerImpl {
+    on_changed_port: Option<Isolate>,
+}
+
+impl KeyStoreHandlerImpl {
+    pub fn new(on_changed_port: i64) -> Self {
+        Self {
+            on_changed_port: (on_changed_port != 0).then(|| Isolate::new(on_changed_port)),
+        }
+    }
+
+    pub fn on_changed(&self, event: KeyStoreEvent) {
+        if let Some(on_changed_port) = &self.on_changed_port {
+            let payload = serde_json::to_string(&event).unwrap();
+
+            on_changed_port.post(payload);
+        }
+    }
+}
@@ -1,12 +1,21 @@
+mod handler;
+mod models;
+
 use std::{
-    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    ops::Deref,
+    os::raw::{c_char, c_longlong, c_uint, c_ulonglong, c_void},
     sync::Arc,
     time::Duration,
 };
 
 use allo_isolate::Isolate;
+use bip39::Language;
+use ed25519_dalek::Signer;
 use nekoton::{
-    core::keystore::{KeyStore, KeyStoreBuilder},
+    core::{
+        keystore::{KeyStore, KeyStoreBuilder},
+        models::Transaction,
+    },
     crypto::{
         DerivedKeyCreateInput, DerivedKeyExportParams, DerivedKeyGetPublicKeys,
         DerivedKeySignParams, DerivedKeySigner, DerivedKeyUpdateParams, EncryptedData,
@@ -16,26 +25,42 @@ use nekoton::{
     },
     external::Storage,
 };
+use secstr::SecUtf8;
 use sha2::Digest;
+use zeroize::Zeroizing;
 
 use crate::{
+    core::keystore::{
+        handler::KeyStoreHandlerImpl,
+        models::{EncryptedDataRecipient, EncryptedPayload, ExportKeyOutput, KeyStoreEvent},
+    },
     crypto::{
-        derived_key::DERIVED_KEY_SIGNER_NAME,
+        check_raw_signing_data,
+        derived_key::{
+            derive_keypair, derive_public_keys_by_path,
+            models::{DerivationPathParams, DerivationPathRange},
+            phrase_to_seed, DERIVED_KEY_SIGNER_NAME,
+        },
+        encrypted_comment::{
+            pack_encrypted_comment, transaction_encrypted_comments, unpack_encrypted_comment,
+        },
         encrypted_key::{
             EncryptedKeyCreateInputHelper, EncryptedKeyExportOutputHelper,
             ENCRYPTED_KEY_SIGNER_NAME,
         },
         ledger_key::LEDGER_KEY_SIGNER_NAME,
-        models::{SignatureParts, SignedData, SignedDataRaw},
+        models::{SignatureParts, SignedData, SignedDataRaw, SignedTypedMessage, TypedMessage},
+        typed_message_preimage,
     },
     external::{ledger_connection::LedgerConnectionImpl, storage::StorageImpl},
-    parse_public_key, runtime, HandleError, MatchResult, PostWithResult, ToPtrAddress,
-    ToStringFromPtr, RUNTIME,
+    parse_public_key, runtime, HandleError, MatchResult, MatchSecretResult, PostWithResult,
+    ToPtrAddress, ToSecretStringFromPtr, ToStringFromPtr, RUNTIME,
 };
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_keystore_create(
     result_port: c_longlong,
+    on_changed_port: c_longlong,
     storage: *mut c_void,
     connection: *mut c_void,
     signers: *mut c_char,
@@ -51,6 +76,7 @@ pub unsafe extern "C" fn nt_keystore_create(
 
     runtime!().spawn(async move {
         async fn internal_fn(
+            on_changed_port: i64,
             storage: Arc<dyn Storage>,
             connection: Option<Arc<LedgerConnectionImpl>>,
             signers: String,
@@ -61,12 +87,14 @@ pub unsafe extern "C" fn nt_keystore_create(
 
             let keystore = keystore_builder.load(storage).await.handle_error()?;
 
-            let ptr = Box::into_raw(Box::new(keystore));
+            let handler = KeyStoreHandlerImpl::new(on_changed_port);
+
+            let ptr = Box::into_raw(Box::new(KeyStoreImpl { keystore, handler }));
 
             serde_json::to_value(ptr.to_ptr_address()).handle_error()
         }
 
-        let result = internal_fn(storage, connection, signers)
+        let result = internal_fn(on_changed_port, storage, connection, signers)
             .await
             .match_result();
 
@@ -78,7 +106,7 @@ pub unsafe extern "C" fn nt_keystore_create(
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_keystore_entries(result_port: c_longlong, keystore: *mut c_void) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     runtime!().spawn(async move {
         async fn internal_fn(keystore: &KeyStore) -> Result<serde_json::Value, String> {
@@ -102,19 +130,19 @@ pub unsafe extern "C" fn nt_keystore_add_key(
     signer: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            keystore: &KeyStore,
+            keystore: &KeyStoreImpl,
             signer: String,
-            input: String,
+            input: SecUtf8,
         ) -> Result<serde_json::Value, String> {
             let entry = if signer == ENCRYPTED_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<EncryptedKeyCreateInputHelper>(&input)
+                let input = serde_json::from_str::<EncryptedKeyCreateInputHelper>(input.unsecure())
                     .map(
                         |EncryptedKeyCreateInputHelper(encrypted_key_create_input)| {
                             encrypted_key_create_input
@@ -127,14 +155,16 @@ pub unsafe extern "C" fn nt_keystore_add_key(
                     .await
                     .handle_error()?
             } else if signer == DERIVED_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<DerivedKeyCreateInput>(&input).handle_error()?;
+                let input = serde_json::from_str::<DerivedKeyCreateInput>(input.unsecure())
+                    .handle_error()?;
 
                 keystore
                     .add_key::<DerivedKeySigner>(input)
                     .await
                     .handle_error()?
             } else if signer == LEDGER_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<LedgerKeyCreateInput>(&input).handle_error()?;
+                let input = serde_json::from_str::<LedgerKeyCreateInput>(input.unsecure())
+                    .handle_error()?;
 
                 keystore
                     .add_key::<LedgerKeySigner>(input)
@@ -144,7 +174,13 @@ pub unsafe extern "C" fn nt_keystore_add_key(
                 panic!()
             };
 
-            serde_json::to_value(&entry).handle_error()
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            keystore
+                .handler
+                .on_changed(KeyStoreEvent::KeysAdded(vec![entry]));
+
+            Ok(value)
         }
 
         let result = internal_fn(keystore, signer, input).await.match_result();
@@ -162,43 +198,44 @@ pub unsafe extern "C" fn nt_keystore_add_keys(
     signer: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            keystore: &KeyStore,
+            keystore: &KeyStoreImpl,
             signer: String,
-            input: String,
+            input: SecUtf8,
         ) -> Result<serde_json::Value, String> {
             let entries = if signer == ENCRYPTED_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<Vec<EncryptedKeyCreateInputHelper>>(&input)
-                    .handle_error()?
-                    .into_iter()
-                    .map(
-                        |EncryptedKeyCreateInputHelper(encrypted_key_create_input)| {
-                            encrypted_key_create_input
-                        },
-                    )
-                    .collect::<Vec<_>>();
+                let input =
+                    serde_json::from_str::<Vec<EncryptedKeyCreateInputHelper>>(input.unsecure())
+                        .handle_error()?
+                        .into_iter()
+                        .map(
+                            |EncryptedKeyCreateInputHelper(encrypted_key_create_input)| {
+                                encrypted_key_create_input
+                            },
+                        )
+                        .collect::<Vec<_>>();
 
                 keystore
                     .add_keys::<EncryptedKeySigner, Vec<EncryptedKeyCreateInput>>(input)
                     .await
                     .handle_error()?
             } else if signer == DERIVED_KEY_SIGNER_NAME {
-                let input =
-                    serde_json::from_str::<Vec<DerivedKeyCreateInput>>(&input).handle_error()?;
+                let input = serde_json::from_str::<Vec<DerivedKeyCreateInput>>(input.unsecure())
+                    .handle_error()?;
 
                 keystore
                     .add_keys::<DerivedKeySigner, Vec<DerivedKeyCreateInput>>(input)
                     .await
                     .handle_error()?
             } else if signer == LEDGER_KEY_SIGNER_NAME {
-                let input =
-                    serde_json::from_str::<Vec<LedgerKeyCreateInput>>(&input).handle_error()?;
+                let input = serde_json::from_str::<Vec<LedgerKeyCreateInput>>(input.unsecure())
+                    .handle_error()?;
 
                 keystore
                     .add_keys::<LedgerKeySigner, Vec<LedgerKeyCreateInput>>(input)
@@ -208,7 +245,13 @@ pub unsafe extern "C" fn nt_keystore_add_keys(
                 panic!()
             };
 
-            serde_json::to_value(&entries).handle_error()
+            let value = serde_json::to_value(&entries).handle_error()?;
+
+            keystore
+                .handler
+                .on_changed(KeyStoreEvent::KeysAdded(entries));
+
+            Ok(value)
         }
 
         let result = internal_fn(keystore, signer, input).await.match_result();
@@ -226,35 +269,36 @@ pub unsafe extern "C" fn nt_keystore_update_key(
     signer: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            keystore: &KeyStore,
+            keystore: &KeyStoreImpl,
             signer: String,
-            input: String,
+            input: SecUtf8,
         ) -> Result<serde_json::Value, String> {
             let entry = if signer == ENCRYPTED_KEY_SIGNER_NAME {
-                let input =
-                    serde_json::from_str::<EncryptedKeyUpdateParams>(&input).handle_error()?;
+                let input = serde_json::from_str::<EncryptedKeyUpdateParams>(input.unsecure())
+                    .handle_error()?;
 
                 keystore
                     .update_key::<EncryptedKeySigner>(input)
                     .await
                     .handle_error()?
             } else if signer == DERIVED_KEY_SIGNER_NAME {
-                let input =
-                    serde_json::from_str::<DerivedKeyUpdateParams>(&input).handle_error()?;
+                let input = serde_json::from_str::<DerivedKeyUpdateParams>(input.unsecure())
+                    .handle_error()?;
 
                 keystore
                     .update_key::<DerivedKeySigner>(input)
                     .await
                     .handle_error()?
             } else if signer == LEDGER_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<LedgerUpdateKeyInput>(&input).handle_error()?;
+                let input = serde_json::from_str::<LedgerUpdateKeyInput>(input.unsecure())
+                    .handle_error()?;
 
                 keystore
                     .update_key::<LedgerKeySigner>(input)
@@ -264,7 +308,13 @@ pub unsafe extern "C" fn nt_keystore_update_key(
                 panic!()
             };
 
-            serde_json::to_value(&entry).handle_error()
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            keystore
+                .handler
+                .on_changed(KeyStoreEvent::KeysUpdated(vec![entry]));
+
+            Ok(value)
         }
 
         let result = internal_fn(keystore, signer, input).await.match_result();
@@ -282,42 +332,145 @@ pub unsafe extern "C" fn nt_keystore_export_key(
     signer: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
             keystore: &KeyStore,
             signer: String,
-            input: String,
-        ) -> Result<serde_json::Value, String> {
+            input: SecUtf8,
+        ) -> Result<ExportKeyOutput, String> {
             if signer == ENCRYPTED_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<EncryptedKeyPassword>(&input).handle_error()?;
+                let input = serde_json::from_str::<EncryptedKeyPassword>(input.unsecure())
+                    .handle_error()?;
 
                 let output = keystore
                     .export_key::<EncryptedKeySigner>(input)
                     .await
                     .handle_error()?;
 
-                serde_json::to_value(&EncryptedKeyExportOutputHelper(output)).handle_error()
+                Ok(ExportKeyOutput::Encrypted(EncryptedKeyExportOutputHelper(
+                    output,
+                )))
             } else if signer == DERIVED_KEY_SIGNER_NAME {
-                let input =
-                    serde_json::from_str::<DerivedKeyExportParams>(&input).handle_error()?;
+                let input = serde_json::from_str::<DerivedKeyExportParams>(input.unsecure())
+                    .handle_error()?;
 
                 let output = keystore
                     .export_key::<DerivedKeySigner>(input)
                     .await
                     .handle_error()?;
 
-                serde_json::to_value(&output).handle_error()
+                Ok(ExportKeyOutput::Derived(output))
             } else {
                 panic!()
             }
         }
 
-        let result = internal_fn(keystore, signer, input).await.match_result();
+        let result = internal_fn(keystore, signer, input)
+            .await
+            .match_secret_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_keystore_derive_public_keys_by_path(
+    result_port: c_longlong,
+    keystore: *mut c_void,
+    input: *mut c_char,
+    range: *mut c_char,
+) {
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+
+    let input = input.to_secret_string_from_ptr();
+    let range = range.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            keystore: &KeyStore,
+            input: SecUtf8,
+            range: String,
+        ) -> Result<serde_json::Value, String> {
+            let input =
+                serde_json::from_str::<DerivedKeyExportParams>(input.unsecure()).handle_error()?;
+
+            let range = serde_json::from_str::<DerivationPathRange>(&range).handle_error()?;
+
+            let output = keystore
+                .export_key::<DerivedKeySigner>(input)
+                .await
+                .handle_error()?;
+
+            let public_keys =
+                derive_public_keys_by_path(output.phrase.unsecure(), Language::English, &range)?;
+
+            serde_json::to_value(&public_keys).handle_error()
+        }
+
+        let result = internal_fn(keystore, input, range).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+// Keys derived by arbitrary paths aren't stored in the keystore, they are
+// derived from the stored master key on every signing
+#[no_mangle]
+pub unsafe extern "C" fn nt_keystore_sign_by_path(
+    result_port: c_longlong,
+    keystore: *mut c_void,
+    input: *mut c_char,
+    params: *mut c_char,
+    data: *mut c_char,
+) {
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+
+    let input = input.to_secret_string_from_ptr();
+    let params = params.to_string_from_ptr();
+    let data = data.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            keystore: &KeyStore,
+            input: SecUtf8,
+            params: String,
+            data: String,
+        ) -> Result<serde_json::Value, String> {
+            let input =
+                serde_json::from_str::<DerivedKeyExportParams>(input.unsecure()).handle_error()?;
+
+            let params = serde_json::from_str::<DerivationPathParams>(&params).handle_error()?;
+
+            let data = base64::decode(&data).handle_error()?;
+
+            check_raw_signing_data(&data)?;
+
+            let output = keystore
+                .export_key::<DerivedKeySigner>(input)
+                .await
+                .handle_error()?;
+
+            let seed = phrase_to_seed(output.phrase.unsecure(), Language::English)?;
+
+            let keypair = derive_keypair(seed.as_bytes(), &params.path, params.scheme)?;
+
+            let signature = base64::encode(&keypair.sign(&data).to_bytes());
+
+            serde_json::to_value(signature).handle_error()
+        }
+
+        let result = internal_fn(keystore, input, params, data)
+            .await
+            .match_result();
 
         Isolate::new(result_port)
             .post_with_result(result.to_ptr_address())
@@ -332,20 +485,20 @@ pub unsafe extern "C" fn nt_keystore_get_public_keys(
     signer: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
             keystore: &KeyStore,
             signer: String,
-            input: String,
+            input: SecUtf8,
         ) -> Result<serde_json::Value, String> {
             if signer == ENCRYPTED_KEY_SIGNER_NAME {
-                let input =
-                    serde_json::from_str::<EncryptedKeyGetPublicKeys>(&input).handle_error()?;
+                let input = serde_json::from_str::<EncryptedKeyGetPublicKeys>(input.unsecure())
+                    .handle_error()?;
 
                 let output = keystore
                     .get_public_keys::<EncryptedKeySigner>(input)
@@ -357,8 +510,8 @@ pub unsafe extern "C" fn nt_keystore_get_public_keys(
 
                 serde_json::to_value(&output).handle_error()
             } else if signer == DERIVED_KEY_SIGNER_NAME {
-                let input =
-                    serde_json::from_str::<DerivedKeyGetPublicKeys>(&input).handle_error()?;
+                let input = serde_json::from_str::<DerivedKeyGetPublicKeys>(input.unsecure())
+                    .handle_error()?;
 
                 let output = keystore
                     .get_public_keys::<DerivedKeySigner>(input)
@@ -370,8 +523,8 @@ pub unsafe extern "C" fn nt_keystore_get_public_keys(
 
                 serde_json::to_value(&output).handle_error()
             } else if signer == LEDGER_KEY_SIGNER_NAME {
-                let input =
-                    serde_json::from_str::<LedgerKeyGetPublicKeys>(&input).handle_error()?;
+                let input = serde_json::from_str::<LedgerKeyGetPublicKeys>(input.unsecure())
+                    .handle_error()?;
 
                 let output = keystore
                     .get_public_keys::<LedgerKeySigner>(input)
@@ -405,24 +558,24 @@ pub unsafe extern "C" fn nt_keystore_encrypt(
     algorithm: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
-    let data = data.to_string_from_ptr();
+    let data = data.to_secret_string_from_ptr();
     let public_keys = public_keys.to_string_from_ptr();
     let algorithm = algorithm.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
             keystore: &KeyStore,
             signer: String,
-            data: String,
+            data: SecUtf8,
             public_keys: String,
             algorithm: String,
-            input: String,
+            input: SecUtf8,
         ) -> Result<serde_json::Value, String> {
-            let data = base64::decode(data).handle_error()?;
+            let data = Zeroizing::new(base64::decode(data.unsecure()).handle_error()?);
 
             let public_keys = serde_json::from_str::<Vec<&str>>(&public_keys)
                 .handle_error()?
@@ -433,30 +586,7 @@ pub unsafe extern "C" fn nt_keystore_encrypt(
             let algorithm =
                 serde_json::from_str::<EncryptionAlgorithm>(&algorithm).handle_error()?;
 
-            let data = if signer == ENCRYPTED_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<EncryptedKeyPassword>(&input).handle_error()?;
-
-                keystore
-                    .encrypt::<EncryptedKeySigner>(&data, &public_keys, algorithm, input)
-                    .await
-                    .handle_error()?
-            } else if signer == DERIVED_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<DerivedKeySignParams>(&input).handle_error()?;
-
-                keystore
-                    .encrypt::<DerivedKeySigner>(&data, &public_keys, algorithm, input)
-                    .await
-                    .handle_error()?
-            } else if signer == LEDGER_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<LedgerSignInput>(&input).handle_error()?;
-
-                keystore
-                    .encrypt::<LedgerKeySigner>(&data, &public_keys, algorithm, input)
-                    .await
-                    .handle_error()?
-            } else {
-                panic!()
-            };
+            let data = encrypt(keystore, signer, &data, &public_keys, algorithm, input).await?;
 
             serde_json::to_value(&data).handle_error()
         }
@@ -479,52 +609,123 @@ pub unsafe extern "C" fn nt_keystore_decrypt(
     data: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
     let data = data.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
             keystore: &KeyStore,
             signer: String,
             data: String,
-            input: String,
-        ) -> Result<serde_json::Value, String> {
+            input: SecUtf8,
+        ) -> Result<SecUtf8, String> {
             let data = serde_json::from_str::<EncryptedData>(&data).handle_error()?;
 
-            let data = if signer == ENCRYPTED_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<EncryptedKeyPassword>(&input).handle_error()?;
+            let data = decrypt(keystore, signer, &data, input).await?;
 
-                keystore
-                    .decrypt::<EncryptedKeySigner>(&data, input)
-                    .await
-                    .handle_error()?
-            } else if signer == DERIVED_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<DerivedKeySignParams>(&input).handle_error()?;
+            let data = SecUtf8::from(base64::encode(&*data));
 
-                keystore
-                    .decrypt::<DerivedKeySigner>(&data, input)
-                    .await
-                    .handle_error()?
-            } else if signer == LEDGER_KEY_SIGNER_NAME {
-                let input = serde_json::from_str::<LedgerSignInput>(&input).handle_error()?;
+            Ok(data)
+        }
 
-                keystore
-                    .decrypt::<LedgerKeySigner>(&data, input)
-                    .await
-                    .handle_error()?
-            } else {
-                panic!()
-            };
+        let result = internal_fn(keystore, signer, data, input)
+            .await
+            .match_secret_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
 
-            let data = base64::encode(&data);
+#[no_mangle]
+pub unsafe extern "C" fn nt_keystore_encrypt_comment(
+    result_port: c_longlong,
+    keystore: *mut c_void,
+    signer: *mut c_char,
+    comment: *mut c_char,
+    public_key: *mut c_char,
+    input: *mut c_char,
+) {
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+
+    let signer = signer.to_string_from_ptr();
+    let comment = comment.to_string_from_ptr();
+    let public_key = public_key.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
-            serde_json::to_value(data).handle_error()
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            keystore: &KeyStore,
+            signer: String,
+            comment: String,
+            public_key: String,
+            input: SecUtf8,
+        ) -> Result<serde_json::Value, String> {
+            let public_key = parse_public_key(&public_key)?;
+
+            let data = encrypt(
+                keystore,
+                signer,
+                comment.as_bytes(),
+                &[public_key],
+                EncryptionAlgorithm::ChaCha20Poly1305,
+                input,
+            )
+            .await?
+            .pop()
+            .ok_or("Comment wasn't encrypted")
+            .handle_error()?;
+
+            let payload = pack_encrypted_comment(&data)?;
+
+            serde_json::to_value(payload).handle_error()
         }
 
-        let result = internal_fn(keystore, signer, data, input)
+        let result = internal_fn(keystore, signer, comment, public_key, input)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_keystore_decrypt_comment(
+    result_port: c_longlong,
+    keystore: *mut c_void,
+    signer: *mut c_char,
+    payload: *mut c_char,
+    input: *mut c_char,
+) {
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+
+    let signer = signer.to_string_from_ptr();
+    let payload = payload.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            keystore: &KeyStore,
+            signer: String,
+            payload: String,
+            input: SecUtf8,
+        ) -> Result<serde_json::Value, String> {
+            let data = unpack_encrypted_comment(&payload)?;
+
+            let comment = decrypt(keystore, signer, &data, input).await?;
+
+            let comment = String::from_utf8(comment.to_vec()).handle_error()?;
+
+            serde_json::to_value(comment).handle_error()
+        }
+
+        let result = internal_fn(keystore, signer, payload, input)
             .await
             .match_result();
 
@@ -534,6 +735,204 @@ pub unsafe extern "C" fn nt_keystore_decrypt(
     });
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn nt_keystore_decrypt_transaction_comments(
+    result_port: c_longlong,
+    keystore: *mut c_void,
+    signer: *mut c_char,
+    transaction: *mut c_char,
+    input: *mut c_char,
+) {
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+
+    let signer = signer.to_string_from_ptr();
+    let transaction = transaction.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            keystore: &KeyStore,
+            signer: String,
+            transaction: String,
+            input: SecUtf8,
+        ) -> Result<Vec<SecUtf8>, String> {
+            let transaction = serde_json::from_str::<Transaction>(&transaction).handle_error()?;
+
+            let public_keys = keystore
+                .get_entries()
+                .await
+                .into_iter()
+                .filter(|e| e.signer_name == signer)
+                .map(|e| e.public_key)
+                .collect::<Vec<_>>();
+
+            let mut comments = Vec::new();
+
+            for mut data in transaction_encrypted_comments(&transaction)? {
+                // Shared secret is symmetric, so the sender decrypts its own comment
+                // using the recipient as the counterparty
+                if !public_keys.contains(&data.recipient_public_key) {
+                    if !public_keys.contains(&data.source_public_key) {
+                        continue;
+                    }
+
+                    std::mem::swap(&mut data.source_public_key, &mut data.recipient_public_key);
+                }
+
+                let comment = decrypt(keystore, signer.clone(), &data, input.clone()).await?;
+
+                let comment = std::str::from_utf8(&comment).handle_error()?;
+
+                comments.push(SecUtf8::from(comment));
+            }
+
+            Ok(comments)
+        }
+
+        let result = internal_fn(keystore, signer, transaction, input)
+            .await
+            .match_secret_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_keystore_reencrypt(
+    result_port: c_longlong,
+    keystore: *mut c_void,
+    signer: *mut c_char,
+    public_key: *mut c_char,
+    data: *mut c_char,
+    public_keys: *mut c_char,
+    algorithm: *mut c_char,
+    replace: c_uint,
+    input: *mut c_char,
+) {
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+
+    let signer = signer.to_string_from_ptr();
+    let public_key = public_key.to_string_from_ptr();
+    let data = data.to_string_from_ptr();
+    let public_keys = public_keys.to_string_from_ptr();
+    let algorithm = algorithm.to_string_from_ptr();
+    let replace = replace != 0;
+    let input = input.to_secret_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            keystore: &KeyStore,
+            signer: String,
+            public_key: String,
+            data: String,
+            public_keys: String,
+            algorithm: String,
+            replace: bool,
+            input: SecUtf8,
+        ) -> Result<serde_json::Value, String> {
+            let data = match serde_json::from_str::<EncryptedPayload>(&data).handle_error()? {
+                EncryptedPayload::Single(data) => vec![data],
+                EncryptedPayload::Multiple(data) => data,
+            };
+
+            let public_keys = serde_json::from_str::<Vec<&str>>(&public_keys)
+                .handle_error()?
+                .into_iter()
+                .map(parse_public_key)
+                .collect::<Result<Vec<_>, String>>()?;
+
+            let algorithm =
+                serde_json::from_str::<EncryptionAlgorithm>(&algorithm).handle_error()?;
+
+            let public_key = parse_public_key(&public_key)?;
+
+            let entry = data
+                .iter()
+                .find(|e| e.recipient_public_key == public_key)
+                .ok_or("Data isn't encrypted for this key")
+                .handle_error()?;
+
+            let decrypted = decrypt(keystore, signer.clone(), entry, input.clone()).await?;
+
+            let (mut result, public_keys) = match replace {
+                true => (vec![], public_keys),
+                false => {
+                    let public_keys = public_keys
+                        .into_iter()
+                        .filter(|public_key| {
+                            !data.iter().any(|e| e.recipient_public_key == *public_key)
+                        })
+                        .collect::<Vec<_>>();
+
+                    (data, public_keys)
+                },
+            };
+
+            if !public_keys.is_empty() {
+                let encrypted =
+                    encrypt(keystore, signer, &decrypted, &public_keys, algorithm, input).await?;
+
+                result.extend(encrypted);
+            }
+
+            serde_json::to_value(&result).handle_error()
+        }
+
+        let result = internal_fn(
+            keystore,
+            signer,
+            public_key,
+            data,
+            public_keys,
+            algorithm,
+            replace,
+            input,
+        )
+        .await
+        .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_get_encrypted_data_recipients(data: *mut c_char) -> *mut c_char {
+    let data = data.to_string_from_ptr();
+
+    fn internal_fn(data: String) -> Result<serde_json::Value, String> {
+        let data = match serde_json::from_str::<EncryptedPayload>(&data).handle_error()? {
+            EncryptedPayload::Single(data) => vec![data],
+            EncryptedPayload::Multiple(data) => data,
+        };
+
+        let recipients = data
+            .into_iter()
+            .map(|e| EncryptedDataRecipient {
+                algorithm: e.algorithm,
+                source_public_key: e.source_public_key,
+                recipient_public_key: e.recipient_public_key,
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_value(&recipients).handle_error()
+    }
+
+    internal_fn(data).match_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_get_encryption_algorithms() -> *mut c_char {
+    fn internal_fn() -> Result<serde_json::Value, String> {
+        serde_json::to_value(&[EncryptionAlgorithm::ChaCha20Poly1305]).handle_error()
+    }
+
+    internal_fn().match_result()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn nt_keystore_sign(
     result_port: c_longlong,
@@ -542,21 +941,23 @@ pub unsafe extern "C" fn nt_keystore_sign(
     data: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
     let data = data.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
             keystore: &KeyStore,
             signer: String,
             data: String,
-            input: String,
+            input: SecUtf8,
         ) -> Result<serde_json::Value, String> {
             let data = base64::decode(&data).handle_error()?;
 
+            check_raw_signing_data(&data)?;
+
             let signature = sign(keystore, signer, &data, input).await?;
 
             let signature = base64::encode(&signature);
@@ -582,18 +983,18 @@ pub unsafe extern "C" fn nt_keystore_sign_data(
     data: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
     let data = data.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
             keystore: &KeyStore,
             signer: String,
             data: String,
-            input: String,
+            input: SecUtf8,
         ) -> Result<serde_json::Value, String> {
             let data = base64::decode(data).handle_error()?;
             let hash: [u8; 32] = sha2::Sha256::digest(&data).into();
@@ -623,6 +1024,56 @@ pub unsafe extern "C" fn nt_keystore_sign_data(
     });
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn nt_keystore_sign_typed_message(
+    result_port: c_longlong,
+    keystore: *mut c_void,
+    signer: *mut c_char,
+    message: *mut c_char,
+    input: *mut c_char,
+) {
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+
+    let signer = signer.to_string_from_ptr();
+    let message = message.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            keystore: &KeyStore,
+            signer: String,
+            message: String,
+            input: SecUtf8,
+        ) -> Result<serde_json::Value, String> {
+            let message = serde_json::from_str::<TypedMessage>(&message).handle_error()?;
+
+            let preimage = typed_message_preimage(&message)?;
+
+            let signature = sign(keystore, signer, &preimage, input).await?;
+
+            let signed_typed_message = SignedTypedMessage {
+                preimage: base64::encode(&preimage),
+                signature: base64::encode(&signature),
+                signature_hex: hex::encode(&signature),
+                signature_parts: SignatureParts {
+                    high: format!("0x{}", hex::encode(&signature[..32])),
+                    low: format!("0x{}", hex::encode(&signature[32..])),
+                },
+            };
+
+            serde_json::to_value(&signed_typed_message).handle_error()
+        }
+
+        let result = internal_fn(keystore, signer, message, input)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn nt_keystore_sign_data_raw(
     result_port: c_longlong,
@@ -631,21 +1082,23 @@ pub unsafe extern "C" fn nt_keystore_sign_data_raw(
     data: *mut c_char,
     input: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let signer = signer.to_string_from_ptr();
     let data = data.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
             keystore: &KeyStore,
             signer: String,
             data: String,
-            input: String,
+            input: SecUtf8,
         ) -> Result<serde_json::Value, String> {
             let data = base64::decode(data).handle_error()?;
 
+            check_raw_signing_data(&data)?;
+
             let signature = sign(keystore, signer, &data, input).await?;
 
             let signed_data_raw = SignedDataRaw {
@@ -676,20 +1129,28 @@ pub unsafe extern "C" fn nt_keystore_remove_key(
     keystore: *mut c_void,
     public_key: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let public_key = public_key.to_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            keystore: &KeyStore,
+            keystore: &KeyStoreImpl,
             public_key: String,
         ) -> Result<serde_json::Value, String> {
             let public_key = parse_public_key(&public_key)?;
 
             let entry = keystore.remove_key(&public_key).await.handle_error()?;
 
-            serde_json::to_value(entry).handle_error()
+            let value = serde_json::to_value(&entry).handle_error()?;
+
+            if let Some(entry) = entry {
+                keystore
+                    .handler
+                    .on_changed(KeyStoreEvent::KeysRemoved(vec![entry]));
+            }
+
+            Ok(value)
         }
 
         let result = internal_fn(keystore, public_key).await.match_result();
@@ -706,13 +1167,13 @@ pub unsafe extern "C" fn nt_keystore_remove_keys(
     keystore: *mut c_void,
     public_keys: *mut c_char,
 ) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let public_keys = public_keys.to_string_from_ptr();
 
     runtime!().spawn(async move {
         async fn internal_fn(
-            keystore: &KeyStore,
+            keystore: &KeyStoreImpl,
             public_keys: String,
         ) -> Result<serde_json::Value, String> {
             let public_keys = serde_json::from_str::<Vec<&str>>(&public_keys)
@@ -723,7 +1184,13 @@ pub unsafe extern "C" fn nt_keystore_remove_keys(
 
             let entries = keystore.remove_keys(&public_keys).await.handle_error()?;
 
-            serde_json::to_value(&entries).handle_error()
+            let value = serde_json::to_value(&entries).handle_error()?;
+
+            keystore
+                .handler
+                .on_changed(KeyStoreEvent::KeysRemoved(entries));
+
+            Ok(value)
         }
 
         let result = internal_fn(keystore, public_keys).await.match_result();
@@ -740,7 +1207,7 @@ pub unsafe extern "C" fn nt_keystore_is_password_cached(
     public_key: *mut c_char,
     duration: c_ulonglong,
 ) -> *mut c_char {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     let public_key = public_key.to_string_from_ptr();
 
@@ -763,12 +1230,14 @@ pub unsafe extern "C" fn nt_keystore_is_password_cached(
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_keystore_clear(result_port: c_longlong, keystore: *mut c_void) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     runtime!().spawn(async move {
-        async fn internal_fn(keystore: &KeyStore) -> Result<serde_json::Value, String> {
+        async fn internal_fn(keystore: &KeyStoreImpl) -> Result<serde_json::Value, String> {
             keystore.clear().await.handle_error()?;
 
+            keystore.handler.on_changed(KeyStoreEvent::Cleared);
+
             Ok(serde_json::Value::Null)
         }
 
@@ -782,12 +1251,14 @@ pub unsafe extern "C" fn nt_keystore_clear(result_port: c_longlong, keystore: *m
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_keystore_reload(result_port: c_longlong, keystore: *mut c_void) {
-    let keystore = &*(keystore as *mut KeyStore);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
 
     runtime!().spawn(async move {
-        async fn internal_fn(keystore: &KeyStore) -> Result<serde_json::Value, String> {
+        async fn internal_fn(keystore: &KeyStoreImpl) -> Result<serde_json::Value, String> {
             keystore.reload().await.handle_error()?;
 
+            keystore.handler.on_changed(KeyStoreEvent::Reloaded);
+
             Ok(serde_json::Value::Null)
         }
 
@@ -831,28 +1302,30 @@ pub unsafe extern "C" fn nt_keystore_verify_data(
     internal_fn(connection, signers, data).match_result()
 }
 
-async fn sign(
+pub(crate) async fn sign(
     keystore: &KeyStore,
     signer: String,
     data: &[u8],
-    input: String,
+    input: SecUtf8,
 ) -> Result<Signature, String> {
     if signer == ENCRYPTED_KEY_SIGNER_NAME {
-        let input = serde_json::from_str::<EncryptedKeyPassword>(&input).handle_error()?;
+        let input =
+            serde_json::from_str::<EncryptedKeyPassword>(input.unsecure()).handle_error()?;
 
         keystore
             .sign::<EncryptedKeySigner>(data, input)
             .await
             .handle_error()
     } else if signer == DERIVED_KEY_SIGNER_NAME {
-        let input = serde_json::from_str::<DerivedKeySignParams>(&input).handle_error()?;
+        let input =
+            serde_json::from_str::<DerivedKeySignParams>(input.unsecure()).handle_error()?;
 
         keystore
             .sign::<DerivedKeySigner>(data, input)
             .await
             .handle_error()
     } else if signer == LEDGER_KEY_SIGNER_NAME {
-        let input = serde_json::from_str::<LedgerSignInput>(&input).handle_error()?;
+        let input = serde_json::from_str::<LedgerSignInput>(input.unsecure()).handle_error()?;
 
         keystore
             .sign::<LedgerKeySigner>(data, input)
@@ -863,6 +1336,91 @@ async fn sign(
     }
 }
 
+async fn encrypt(
+    keystore: &KeyStore,
+    signer: String,
+    data: &[u8],
+    public_keys: &[ed25519_dalek::PublicKey],
+    algorithm: EncryptionAlgorithm,
+    input: SecUtf8,
+) -> Result<Vec<EncryptedData>, String> {
+    if signer == ENCRYPTED_KEY_SIGNER_NAME {
+        let input =
+            serde_json::from_str::<EncryptedKeyPassword>(input.unsecure()).handle_error()?;
+
+        keystore
+            .encrypt::<EncryptedKeySigner>(data, public_keys, algorithm, input)
+            .await
+            .handle_error()
+    } else if signer == DERIVED_KEY_SIGNER_NAME {
+        let input =
+            serde_json::from_str::<DerivedKeySignParams>(input.unsecure()).handle_error()?;
+
+        keystore
+            .encrypt::<DerivedKeySigner>(data, public_keys, algorithm, input)
+            .await
+            .handle_error()
+    } else if signer == LEDGER_KEY_SIGNER_NAME {
+        let input = serde_json::from_str::<LedgerSignInput>(input.unsecure()).handle_error()?;
+
+        keystore
+            .encrypt::<LedgerKeySigner>(data, public_keys, algorithm, input)
+            .await
+            .handle_error()
+    } else {
+        panic!()
+    }
+}
+
+async fn decrypt(
+    keystore: &KeyStore,
+    signer: String,
+    data: &EncryptedData,
+    input: SecUtf8,
+) -> Result<Zeroizing<Vec<u8>>, String> {
+    let data = if signer == ENCRYPTED_KEY_SIGNER_NAME {
+        let input =
+            serde_json::from_str::<EncryptedKeyPassword>(input.unsecure()).handle_error()?;
+
+        keystore
+            .decrypt::<EncryptedKeySigner>(data, input)
+            .await
+            .handle_error()?
+    } else if signer == DERIVED_KEY_SIGNER_NAME {
+        let input =
+            serde_json::from_str::<DerivedKeySignParams>(input.unsecure()).handle_error()?;
+
+        keystore
+            .decrypt::<DerivedKeySigner>(data, input)
+            .await
+            .handle_error()?
+    } else if signer == LEDGER_KEY_SIGNER_NAME {
+        let input = serde_json::from_str::<LedgerSignInput>(input.unsecure()).handle_error()?;
+
+        keystore
+            .decrypt::<LedgerKeySigner>(data, input)
+            .await
+            .handle_error()?
+    } else {
+        panic!()
+    };
+
+    Ok(Zeroizing::new(data))
+}
+
+pub(crate) struct KeyStoreImpl {
+    keystore: KeyStore,
+    handler: KeyStoreHandlerImpl,
+}
+
+impl Deref for KeyStoreImpl {
+    type Target = KeyStore;
+
+    fn deref(&self) -> &Self::Target {
+        &self.keystore
+    }
+}
+
 fn map_keystore_builder(
     signers: Vec<String>,
     connection: Option<Arc<LedgerConnectionImpl>>,
@@ -896,5 +1454,5 @@ fn map_keystore_builder(
 #[no_mangle]
 pub unsafe extern "C" fn nt_keystore_free_ptr(ptr: *mut c_void) {
     println!("nt_keystore_free_ptr");
-    Box::from_raw(ptr as *mut KeyStore);
+    Box::from_raw(ptr as *mut KeyStoreImpl);
 }
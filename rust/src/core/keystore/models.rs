@@ -0,0 +1,42 @@
+use nekoton::{
+    core::keystore::KeyStoreEntry,
+    crypto::{DerivedKeyExportOutput, EncryptedData, EncryptionAlgorithm},
+};
+use nekoton_utils::serde_public_key;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::encrypted_key::EncryptedKeyExportOutputHelper;
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum ExportKeyOutput {
+    Encrypted(EncryptedKeyExportOutputHelper),
+    Derived(DerivedKeyExportOutput),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum EncryptedPayload {
+    Single(EncryptedData),
+    Multiple(Vec<EncryptedData>),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedDataRecipient {
+    pub algorithm: EncryptionAlgorithm,
+    #[serde(with = "serde_public_key")]
+    pub source_public_key: ed25519_dalek::PublicKey,
+    #[serde(with = "serde_public_key")]
+    pub recipient_public_key: ed25519_dalek::PublicKey,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum KeyStoreEvent {
+    KeysAdded(Vec<KeyStoreEntry>),
+    KeysUpdated(Vec<KeyStoreEntry>),
+    KeysRemoved(Vec<KeyStoreEntry>),
+    Cleared,
+    Reloaded,
+}
@@ -1,6 +1,12 @@
 mod accounts_storage;
+mod address_book;
+mod contract_classifier;
 mod generic_contract;
 mod keystore;
 mod models;
+mod multisig;
+mod pending_messages;
+mod resend_queue;
+mod send_and_wait;
 mod token_wallet;
 mod ton_wallet;
@@ -0,0 +1,71 @@
+use nekoton::core::ton_wallet::MultisigType;
+
+use crate::HandleError;
+
+const SETCODE_MULTISIG_UPDATE_ABI: &str = r#"{
+    "ABI version": 2,
+    "header": ["pubkey", "time", "expire"],
+    "functions": [
+        {
+            "name": "submitUpdate",
+            "inputs": [
+                {"name": "codeHash", "type": "uint256"},
+                {"name": "owners", "type": "uint256[]"},
+                {"name": "reqConfirms", "type": "uint8"}
+            ],
+            "outputs": [
+                {"name": "updateId", "type": "uint64"}
+            ]
+        },
+        {
+            "name": "confirmUpdate",
+            "inputs": [
+                {"name": "updateId", "type": "uint64"}
+            ],
+            "outputs": []
+        },
+        {
+            "name": "executeUpdate",
+            "inputs": [
+                {"name": "updateId", "type": "uint64"},
+                {"name": "code", "type": "cell"}
+            ],
+            "outputs": []
+        },
+        {
+            "name": "getUpdateRequests",
+            "inputs": [],
+            "outputs": [
+                {
+                    "components": [
+                        {"name": "id", "type": "uint64"},
+                        {"name": "index", "type": "uint8"},
+                        {"name": "signs", "type": "uint8"},
+                        {"name": "confirmationsMask", "type": "uint32"},
+                        {"name": "creator", "type": "uint256"},
+                        {"name": "codeHash", "type": "uint256"},
+                        {"name": "custodians", "type": "uint256[]"},
+                        {"name": "reqConfirms", "type": "uint8"}
+                    ],
+                    "name": "updates",
+                    "type": "tuple[]"
+                }
+            ]
+        }
+    ],
+    "data": [],
+    "events": []
+}"#;
+
+pub fn supports_updates(multisig_type: &MultisigType) -> bool {
+    matches!(
+        multisig_type,
+        MultisigType::SetcodeMultisigWallet
+            | MultisigType::SetcodeMultisigWallet24h
+            | MultisigType::SurfWallet
+    )
+}
+
+pub fn setcode_multisig_update_abi() -> Result<ton_abi::Contract, String> {
+    ton_abi::Contract::load(SETCODE_MULTISIG_UPDATE_ABI).handle_error()
+}
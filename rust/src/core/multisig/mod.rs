@@ -0,0 +1,485 @@
+mod abi;
+mod models;
+pub(crate) mod observer;
+
+use std::{
+    borrow::Cow,
+    os::raw::{c_char, c_longlong, c_void},
+    sync::Arc,
+};
+
+use allo_isolate::Isolate;
+use ed25519_dalek::PublicKey;
+use nekoton::{
+    core::{
+        models::Expiration,
+        parsing::parse_payload,
+        ton_wallet::{TonWallet, WalletType},
+        utils::make_labs_unsigned_message,
+    },
+    transport::models::RawContractState,
+};
+use nekoton_abi::FunctionExt;
+use nekoton_utils::Clock;
+use tokio::sync::RwLock;
+use ton_block::AccountStuff;
+use ton_types::Cell;
+
+use crate::{
+    clock,
+    core::multisig::{
+        abi::{setcode_multisig_update_abi, supports_updates},
+        models::{MultisigProposal, MultisigUpdateParams},
+        observer::MultisigObserver,
+    },
+    parse_public_key, runtime,
+    transport::models::RawContractStateHelper,
+    HandleError, MatchResult, PostWithResult, ToOptionalStringFromPtr, ToPtrAddress,
+    ToStringFromPtr, CLOCK, RUNTIME,
+};
+
+const MAX_CUSTODIANS: usize = 32;
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_multisig_proposals(result_port: c_longlong, ton_wallet: *mut c_void) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+
+    runtime!().spawn(async move {
+        fn internal_fn(ton_wallet: &TonWallet) -> Result<serde_json::Value, String> {
+            let proposals = get_proposals(ton_wallet)?;
+
+            serde_json::to_value(&proposals).handle_error()
+        }
+
+        let ton_wallet = ton_wallet.read().await;
+
+        let result = internal_fn(&ton_wallet).match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_multisig_observer_create(on_event_port: c_longlong) -> *mut c_char {
+    fn internal_fn(on_event_port: i64) -> Result<serde_json::Value, String> {
+        let observer = Arc::new(MultisigObserver::new(on_event_port));
+
+        let ptr = Box::into_raw(Box::new(observer));
+
+        serde_json::to_value(ptr.to_ptr_address()).handle_error()
+    }
+
+    internal_fn(on_event_port).match_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_multisig_observer_update(
+    result_port: c_longlong,
+    observer: *mut c_void,
+    ton_wallet: *mut c_void,
+) {
+    let observer = &*(observer as *mut Arc<MultisigObserver>);
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+
+    runtime!().spawn(async move {
+        fn internal_fn(
+            observer: &Arc<MultisigObserver>,
+            ton_wallet: &TonWallet,
+        ) -> Result<serde_json::Value, String> {
+            let proposals = get_proposals(ton_wallet)?;
+
+            observer.update(ton_wallet, &proposals);
+
+            serde_json::to_value(&proposals).handle_error()
+        }
+
+        let ton_wallet = ton_wallet.read().await;
+
+        let result = internal_fn(observer, &ton_wallet).match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_multisig_observer_free_ptr(ptr: *mut c_void) {
+    println!("nt_multisig_observer_free_ptr");
+    Box::from_raw(ptr as *mut Arc<MultisigObserver>);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_multisig_update_requests(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    contract_state: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+
+    let contract_state = contract_state.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        fn internal_fn(
+            ton_wallet: &TonWallet,
+            contract_state: String,
+        ) -> Result<serde_json::Value, String> {
+            check_updates_supported(ton_wallet)?;
+
+            let account = parse_contract_state(&contract_state)?;
+
+            let contract_abi = setcode_multisig_update_abi()?;
+            let method = contract_abi.function("getUpdateRequests").handle_error()?;
+
+            let output = method
+                .run_local(clock!().as_ref(), account, &[])
+                .handle_error()?;
+
+            let tokens = output
+                .tokens
+                .ok_or("No output")
+                .handle_error()
+                .and_then(|e| nekoton_abi::make_abi_tokens(&e).handle_error())?;
+
+            let updates = tokens
+                .get("updates")
+                .cloned()
+                .unwrap_or_else(|| serde_json::Value::Array(vec![]));
+
+            Ok(updates)
+        }
+
+        let ton_wallet = ton_wallet.read().await;
+
+        let result = internal_fn(&ton_wallet, contract_state).match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_multisig_prepare_submit_update(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    contract_state: *mut c_char,
+    public_key: *mut c_char,
+    params: *mut c_char,
+    expiration: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+
+    let contract_state = contract_state.to_string_from_ptr();
+    let public_key = public_key.to_string_from_ptr();
+    let params = params.to_string_from_ptr();
+    let expiration = expiration.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        fn internal_fn(
+            ton_wallet: &TonWallet,
+            contract_state: String,
+            public_key: String,
+            params: String,
+            expiration: String,
+        ) -> Result<serde_json::Value, String> {
+            check_updates_supported(ton_wallet)?;
+
+            let account = parse_contract_state(&contract_state)?;
+
+            let public_key = parse_public_key(&public_key)?;
+            check_custodian(ton_wallet, &public_key)?;
+
+            let params = serde_json::from_str::<MultisigUpdateParams>(&params).handle_error()?;
+
+            let custodians = params
+                .custodians
+                .iter()
+                .map(|e| parse_public_key(e).map(|e| format!("0x{}", hex::encode(e.as_bytes()))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if custodians.is_empty() || custodians.len() > MAX_CUSTODIANS {
+                return Err("Invalid custodians count").handle_error();
+            }
+
+            if params.req_confirms == 0 || params.req_confirms as usize > custodians.len() {
+                return Err("Invalid required confirmations count").handle_error();
+            }
+
+            let code_hash = match params.code_hash {
+                Some(code_hash) => hex::decode(code_hash.trim_start_matches("0x"))
+                    .ok()
+                    .filter(|e| e.len() == 32)
+                    .map(hex::encode)
+                    .ok_or("Invalid code hash")
+                    .handle_error()?,
+                None => current_code(&account)?.repr_hash().to_hex_string(),
+            };
+
+            let input = serde_json::json!({
+                "codeHash": format!("0x{}", code_hash),
+                "owners": custodians,
+                "reqConfirms": params.req_confirms.to_string(),
+            });
+
+            let expiration = serde_json::from_str::<Expiration>(&expiration).handle_error()?;
+
+            prepare_update_message(ton_wallet, &public_key, "submitUpdate", input, expiration)
+        }
+
+        let ton_wallet = ton_wallet.read().await;
+
+        let result =
+            internal_fn(&ton_wallet, contract_state, public_key, params, expiration).match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_multisig_prepare_confirm_update(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    public_key: *mut c_char,
+    update_id: *mut c_char,
+    expiration: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+
+    let public_key = public_key.to_string_from_ptr();
+    let update_id = update_id.to_string_from_ptr();
+    let expiration = expiration.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        fn internal_fn(
+            ton_wallet: &TonWallet,
+            public_key: String,
+            update_id: String,
+            expiration: String,
+        ) -> Result<serde_json::Value, String> {
+            check_updates_supported(ton_wallet)?;
+
+            let public_key = parse_public_key(&public_key)?;
+            check_custodian(ton_wallet, &public_key)?;
+
+            let update_id = update_id.parse::<u64>().handle_error()?;
+
+            let input = serde_json::json!({
+                "updateId": update_id.to_string(),
+            });
+
+            let expiration = serde_json::from_str::<Expiration>(&expiration).handle_error()?;
+
+            prepare_update_message(ton_wallet, &public_key, "confirmUpdate", input, expiration)
+        }
+
+        let ton_wallet = ton_wallet.read().await;
+
+        let result = internal_fn(&ton_wallet, public_key, update_id, expiration).match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_multisig_prepare_execute_update(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    contract_state: *mut c_char,
+    public_key: *mut c_char,
+    update_id: *mut c_char,
+    code: *mut c_char,
+    expiration: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+
+    let contract_state = contract_state.to_string_from_ptr();
+    let public_key = public_key.to_string_from_ptr();
+    let update_id = update_id.to_string_from_ptr();
+    let code = code.to_optional_string_from_ptr();
+    let expiration = expiration.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        fn internal_fn(
+            ton_wallet: &TonWallet,
+            contract_state: String,
+            public_key: String,
+            update_id: String,
+            code: Option<String>,
+            expiration: String,
+        ) -> Result<serde_json::Value, String> {
+            check_updates_supported(ton_wallet)?;
+
+            let account = parse_contract_state(&contract_state)?;
+
+            let public_key = parse_public_key(&public_key)?;
+            check_custodian(ton_wallet, &public_key)?;
+
+            let update_id = update_id.parse::<u64>().handle_error()?;
+
+            let code = match code {
+                Some(code) => code,
+                None => ton_types::serialize_toc(&current_code(&account)?)
+                    .map(base64::encode)
+                    .handle_error()?,
+            };
+
+            let input = serde_json::json!({
+                "updateId": update_id.to_string(),
+                "code": code,
+            });
+
+            let expiration = serde_json::from_str::<Expiration>(&expiration).handle_error()?;
+
+            prepare_update_message(ton_wallet, &public_key, "executeUpdate", input, expiration)
+        }
+
+        let ton_wallet = ton_wallet.read().await;
+
+        let result = internal_fn(
+            &ton_wallet,
+            contract_state,
+            public_key,
+            update_id,
+            code,
+            expiration,
+        )
+        .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+fn get_proposals(ton_wallet: &TonWallet) -> Result<Vec<MultisigProposal>, String> {
+    if !matches!(ton_wallet.wallet_type(), WalletType::Multisig(_)) {
+        return Err("Not a multisig wallet").handle_error();
+    }
+
+    let lifetime = ton_wallet.details().expiration_time;
+    let now = clock!().now_sec_u64() as u32;
+
+    let custodians = ton_wallet.get_custodians().to_owned().unwrap_or_default();
+
+    let proposals = ton_wallet
+        .get_unconfirmed_transactions()
+        .iter()
+        .cloned()
+        .map(|transaction| {
+            let confirmed_by = transaction
+                .confirmations
+                .iter()
+                .map(|e| e.to_hex_string())
+                .collect::<Vec<_>>();
+
+            let pending_custodians = custodians
+                .iter()
+                .filter(|e| !transaction.confirmations.contains(e))
+                .map(|e| e.to_hex_string())
+                .collect::<Vec<_>>();
+
+            let created_at = (transaction.id >> 32) as u32;
+            let expires_at = created_at.saturating_add(lifetime);
+
+            let known_payload = parse_payload(transaction.payload.clone().into());
+
+            MultisigProposal {
+                transaction,
+                confirmed_by,
+                pending_custodians,
+                created_at,
+                expires_at,
+                time_to_expiry: expires_at.saturating_sub(now),
+                known_payload,
+            }
+        })
+        .collect();
+
+    Ok(proposals)
+}
+
+fn check_updates_supported(ton_wallet: &TonWallet) -> Result<(), String> {
+    match ton_wallet.wallet_type() {
+        WalletType::Multisig(multisig_type) if supports_updates(&multisig_type) => Ok(()),
+        WalletType::Multisig(_) => Err("Multisig type doesn't support updates").handle_error(),
+        _ => Err("Not a multisig wallet").handle_error(),
+    }
+}
+
+fn check_custodian(ton_wallet: &TonWallet, public_key: &PublicKey) -> Result<(), String> {
+    let custodians = ton_wallet
+        .get_custodians()
+        .as_ref()
+        .ok_or("Custodians are not loaded yet")
+        .handle_error()?;
+
+    if !custodians
+        .iter()
+        .any(|e| e.as_slice() == public_key.as_bytes())
+    {
+        return Err("Public key is not a custodian").handle_error();
+    }
+
+    Ok(())
+}
+
+fn parse_contract_state(contract_state: &str) -> Result<AccountStuff, String> {
+    let contract_state = serde_json::from_str::<RawContractStateHelper>(contract_state)
+        .map(|RawContractStateHelper(raw_contract_state)| raw_contract_state)
+        .handle_error()?;
+
+    match contract_state {
+        RawContractState::NotExists => Err("Not exists").handle_error(),
+        RawContractState::Exists(contract) => Ok(contract.account),
+    }
+}
+
+fn current_code(account: &AccountStuff) -> Result<Cell, String> {
+    match &account.storage.state {
+        ton_block::AccountState::AccountActive { state_init } => state_init
+            .code
+            .clone()
+            .ok_or("Account has no code")
+            .handle_error(),
+        _ => Err("Account is not active").handle_error(),
+    }
+}
+
+fn prepare_update_message(
+    ton_wallet: &TonWallet,
+    public_key: &PublicKey,
+    method: &str,
+    input: serde_json::Value,
+    expiration: Expiration,
+) -> Result<serde_json::Value, String> {
+    let contract_abi = setcode_multisig_update_abi()?;
+    let method = contract_abi.function(method).handle_error()?;
+
+    let input = nekoton_abi::parse_abi_tokens(&method.inputs, input).handle_error()?;
+
+    let message = ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
+        dst: ton_wallet.address().clone(),
+        ..Default::default()
+    });
+
+    let unsigned_message = make_labs_unsigned_message(
+        clock!().as_ref(),
+        message,
+        expiration,
+        public_key,
+        Cow::Owned(method.to_owned()),
+        input,
+    )
+    .handle_error()?;
+
+    let ptr = Box::into_raw(Box::new(RwLock::new(unsigned_message)));
+
+    serde_json::to_value(ptr.to_ptr_address()).handle_error()
+}
@@ -0,0 +1,42 @@
+use nekoton::core::models::{KnownPayload, MultisigPendingTransaction};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultisigProposal {
+    pub transaction: MultisigPendingTransaction,
+    pub confirmed_by: Vec<String>,
+    pub pending_custodians: Vec<String>,
+    pub created_at: u32,
+    pub expires_at: u32,
+    pub time_to_expiry: u32,
+    pub known_payload: Option<KnownPayload>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum MultisigEvent {
+    ProposalCreated(MultisigProposalState),
+    ProposalConfirmed(MultisigProposalState),
+    ProposalExecuted(MultisigProposalState),
+    ProposalExpired(MultisigProposalState),
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultisigProposalState {
+    pub address: String,
+    pub transaction_id: String,
+    pub signs_required: u8,
+    pub signs_received: u8,
+    pub confirmed_by: Vec<String>,
+    pub expires_at: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultisigUpdateParams {
+    pub custodians: Vec<String>,
+    pub req_confirms: u8,
+    pub code_hash: Option<String>,
+}
@@ -0,0 +1,289 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use allo_isolate::Isolate;
+use lazy_static::lazy_static;
+use nekoton::core::{
+    models::{
+        MultisigTransaction, TransactionAdditionalInfo, TransactionWithData, TransactionsBatchInfo,
+        TransactionsBatchType, WalletInteractionMethod,
+    },
+    ton_wallet::TonWallet,
+};
+use nekoton_utils::Clock;
+
+use crate::{
+    clock,
+    core::multisig::models::{MultisigEvent, MultisigProposal, MultisigProposalState},
+    CLOCK,
+};
+
+lazy_static! {
+    static ref MULTISIG_OBSERVERS: Mutex<HashMap<String, Vec<Weak<MultisigObserver>>>> =
+        Mutex::new(HashMap::new());
+}
+
+type ProposalKey = (String, u64);
+
+pub struct MultisigObserver {
+    on_event_port: Isolate,
+    state: Mutex<ObserverState>,
+}
+
+#[derive(Default)]
+struct ObserverState {
+    wallets: HashMap<String, WalletParams>,
+    proposals: HashMap<ProposalKey, MultisigProposalState>,
+    // Proposals which are no longer unconfirmed but whose execution wasn't found yet
+    removed: HashMap<ProposalKey, MultisigProposalState>,
+}
+
+#[derive(Default)]
+struct WalletParams {
+    lifetime: u32,
+    signs_required: Option<u8>,
+}
+
+impl MultisigObserver {
+    pub fn new(on_event_port: i64) -> Self {
+        Self {
+            on_event_port: Isolate::new(on_event_port),
+            state: Mutex::new(ObserverState::default()),
+        }
+    }
+
+    pub fn update(self: &Arc<Self>, ton_wallet: &TonWallet, proposals: &[MultisigProposal]) {
+        // Custodians and unconfirmed transactions are loaded together,
+        // so until then an empty list doesn't mean there are no proposals
+        if ton_wallet.get_custodians().is_none() {
+            return;
+        }
+
+        let address = ton_wallet.address().to_string();
+        let now = clock!().now_sec_u64() as u32;
+
+        register_observer(&address, self);
+
+        let mut state = self.state.lock().unwrap();
+
+        let wallet = state.wallets.entry(address.clone()).or_default();
+        wallet.lifetime = ton_wallet.details().expiration_time;
+        if let Some(proposal) = proposals.first() {
+            wallet.signs_required = Some(proposal.transaction.signs_required);
+        }
+
+        let mut current_proposals = HashMap::with_capacity(proposals.len());
+
+        for proposal in proposals {
+            let key = (address.clone(), proposal.transaction.id);
+
+            let proposal_state = MultisigProposalState {
+                address: address.clone(),
+                transaction_id: proposal.transaction.id.to_string(),
+                signs_required: proposal.transaction.signs_required,
+                signs_received: proposal.transaction.signs_received,
+                confirmed_by: proposal.confirmed_by.clone(),
+                expires_at: proposal.expires_at,
+            };
+
+            let known = state
+                .proposals
+                .remove(&key)
+                .or_else(|| state.removed.remove(&key));
+
+            match known {
+                None => self.on_event(MultisigEvent::ProposalCreated(proposal_state.clone())),
+                Some(known) if known.signs_received < proposal_state.signs_received => {
+                    self.on_event(MultisigEvent::ProposalConfirmed(proposal_state.clone()))
+                },
+                _ => {},
+            }
+
+            current_proposals.insert(key, proposal_state);
+        }
+
+        let removed = state
+            .proposals
+            .keys()
+            .filter(|(e, _)| e == &address)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in removed {
+            if let Some(proposal_state) = state.proposals.remove(&key) {
+                state.removed.insert(key, proposal_state);
+            }
+        }
+
+        state.proposals.extend(current_proposals);
+
+        // Execution is reported from wallet transactions, so proposals which left
+        // the list without it are only reported once they can't be executed anymore
+        state.removed.retain(|_, proposal_state| {
+            if now < proposal_state.expires_at {
+                return true;
+            }
+
+            self.on_event(MultisigEvent::ProposalExpired(proposal_state.clone()));
+            false
+        });
+    }
+
+    fn on_transactions_found(
+        &self,
+        address: &str,
+        transactions: &[&TransactionWithData<TransactionAdditionalInfo>],
+        batch_type: &TransactionsBatchType,
+    ) {
+        let mut state = self.state.lock().unwrap();
+
+        for transaction in transactions {
+            let multisig_transaction: &MultisigTransaction = match &transaction.data {
+                Some(TransactionAdditionalInfo::WalletInteraction(info)) => match &info.method {
+                    WalletInteractionMethod::Multisig(multisig_transaction) => multisig_transaction,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+
+            if transaction.transaction.aborted {
+                continue;
+            }
+
+            let executed = transaction
+                .transaction
+                .out_msgs
+                .iter()
+                .any(|e| e.dst.is_some());
+
+            match multisig_transaction {
+                MultisigTransaction::Submit(submit) if !executed => {
+                    // Old batches contain proposals which might be gone already
+                    if !matches!(batch_type, TransactionsBatchType::New) {
+                        continue;
+                    }
+
+                    let key = (address.to_owned(), submit.trans_id);
+
+                    if state.proposals.contains_key(&key) || state.removed.contains_key(&key) {
+                        continue;
+                    }
+
+                    let (lifetime, signs_required) = match state.wallets.get(address) {
+                        Some(WalletParams {
+                            lifetime,
+                            signs_required: Some(signs_required),
+                        }) => (*lifetime, *signs_required),
+                        _ => continue,
+                    };
+
+                    let proposal_state = MultisigProposalState {
+                        address: address.to_owned(),
+                        transaction_id: submit.trans_id.to_string(),
+                        signs_required,
+                        signs_received: 1,
+                        confirmed_by: vec![submit.custodian.to_hex_string()],
+                        expires_at: ((submit.trans_id >> 32) as u32).saturating_add(lifetime),
+                    };
+
+                    self.on_event(MultisigEvent::ProposalCreated(proposal_state.clone()));
+
+                    state.proposals.insert(key, proposal_state);
+                },
+                MultisigTransaction::Confirm(confirm) => {
+                    let key = (address.to_owned(), confirm.transaction_id);
+                    let custodian = confirm.custodian.to_hex_string();
+
+                    if executed {
+                        let proposal_state = state
+                            .proposals
+                            .remove(&key)
+                            .or_else(|| state.removed.remove(&key));
+
+                        if let Some(mut proposal_state) = proposal_state {
+                            if !proposal_state.confirmed_by.contains(&custodian) {
+                                proposal_state.confirmed_by.push(custodian);
+                                proposal_state.signs_received += 1;
+                            }
+
+                            self.on_event(MultisigEvent::ProposalExecuted(proposal_state));
+                        }
+                    } else if let Some(proposal_state) = state.proposals.get_mut(&key) {
+                        if !proposal_state.confirmed_by.contains(&custodian) {
+                            proposal_state.confirmed_by.push(custodian);
+                            proposal_state.signs_received += 1;
+
+                            self.on_event(MultisigEvent::ProposalConfirmed(proposal_state.clone()));
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn on_event(&self, event: MultisigEvent) {
+        let payload = serde_json::to_string(&event).unwrap();
+
+        self.on_event_port.post(payload);
+    }
+}
+
+pub fn notify_transactions_found(
+    transactions: &[TransactionWithData<TransactionAdditionalInfo>],
+    batch_info: &TransactionsBatchInfo,
+) {
+    let mut transactions_by_address = HashMap::<String, Vec<_>>::new();
+
+    for transaction in transactions {
+        if let Some(address) = &transaction.transaction.in_msg.dst {
+            transactions_by_address
+                .entry(address.to_string())
+                .or_default()
+                .push(transaction);
+        }
+    }
+
+    for (address, mut transactions) in transactions_by_address {
+        let observers = match get_observers(&address) {
+            Some(observers) => observers,
+            None => continue,
+        };
+
+        transactions.sort_by_key(|e| e.transaction.id.lt);
+
+        for observer in observers {
+            observer.on_transactions_found(&address, &transactions, &batch_info.batch_type);
+        }
+    }
+}
+
+fn register_observer(address: &str, observer: &Arc<MultisigObserver>) {
+    let mut observers = MULTISIG_OBSERVERS.lock().unwrap();
+
+    let entry = observers.entry(address.to_owned()).or_default();
+
+    entry.retain(|e| e.strong_count() > 0);
+
+    if !entry.iter().any(|e| e.as_ptr() == Arc::as_ptr(observer)) {
+        entry.push(Arc::downgrade(observer));
+    }
+}
+
+fn get_observers(address: &str) -> Option<Vec<Arc<MultisigObserver>>> {
+    let mut observers = MULTISIG_OBSERVERS.lock().unwrap();
+
+    let entry = observers.get_mut(address)?;
+
+    entry.retain(|e| e.strong_count() > 0);
+
+    let result = entry.iter().filter_map(Weak::upgrade).collect::<Vec<_>>();
+
+    if entry.is_empty() {
+        observers.remove(address);
+    }
+
+    Some(result)
+}
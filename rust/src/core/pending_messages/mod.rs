@@ -0,0 +1,542 @@
+pub(crate) mod models;
+pub(crate) mod storage;
+
+use std::{
+    collections::HashMap,
+    os::raw::{c_char, c_longlong, c_void},
+    sync::Arc,
+};
+
+use allo_isolate::Isolate;
+use async_trait::async_trait;
+use nekoton::{
+    core::{
+        generic_contract::GenericContract,
+        models::{PendingTransaction, Transaction},
+        ton_wallet::TonWallet,
+    },
+    crypto::SignedMessage,
+    external::Storage,
+    transport::{models::RawTransaction, Transport},
+};
+use nekoton_utils::Clock;
+use tokio::sync::RwLock;
+use ton_block::{Deserializable, MsgAddressInt, Serializable};
+
+use crate::{
+    clock,
+    core::pending_messages::{
+        models::{
+            DeliveredPendingMessage, FailedPendingMessage, PendingMessage, RestoredPendingMessages,
+        },
+        storage::PendingMessagesStorage,
+    },
+    external::storage::StorageImpl,
+    parse_address, runtime,
+    transport::match_transport,
+    HandleError, MatchResult, PostWithResult, ToPtrAddress, ToStringFromPtr, CLOCK, RUNTIME,
+};
+
+const RESTORE_PAGE_SIZE: u8 = 50;
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_pending_messages_storage_create(
+    result_port: c_longlong,
+    storage: *mut c_void,
+) {
+    let storage = (&*(storage as *mut Arc<StorageImpl>)).clone();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(storage: Arc<dyn Storage>) -> Result<serde_json::Value, String> {
+            let pending_messages = PendingMessagesStorage::load(storage).await?;
+
+            let ptr = Box::into_raw(Box::new(Arc::new(pending_messages)));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        let result = internal_fn(storage).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_pending_messages_storage_entries(
+    result_port: c_longlong,
+    pending_messages: *mut c_void,
+    address: *mut c_char,
+) {
+    let pending_messages = &*(pending_messages as *mut Arc<PendingMessagesStorage>);
+
+    let address = address.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            pending_messages: &PendingMessagesStorage,
+            address: String,
+        ) -> Result<serde_json::Value, String> {
+            let address = parse_address(&address)?.to_string();
+
+            let entries = pending_messages.get(&address).await;
+
+            serde_json::to_value(&entries).handle_error()
+        }
+
+        let result = internal_fn(pending_messages, address).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_pending_messages_storage_clear(
+    result_port: c_longlong,
+    pending_messages: *mut c_void,
+) {
+    let pending_messages = &*(pending_messages as *mut Arc<PendingMessagesStorage>);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            pending_messages: &PendingMessagesStorage,
+        ) -> Result<serde_json::Value, String> {
+            pending_messages.clear().await?;
+
+            Ok(serde_json::Value::Null)
+        }
+
+        let result = internal_fn(pending_messages).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_ton_wallet_send_persistent(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    pending_messages: *mut c_void,
+    signed_message: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+    let pending_messages = &*(pending_messages as *mut Arc<PendingMessagesStorage>);
+
+    let signed_message = signed_message.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            ton_wallet: &mut TonWallet,
+            pending_messages: &PendingMessagesStorage,
+            signed_message: String,
+        ) -> Result<serde_json::Value, String> {
+            let signed_message =
+                serde_json::from_str::<SignedMessage>(&signed_message).handle_error()?;
+
+            let address = ton_wallet.address().to_string();
+            let pending_message = make_pending_message(&signed_message, ton_wallet.latest_lt())?;
+            let message_hash = pending_message.message_hash.clone();
+
+            pending_messages.add(&address, pending_message).await?;
+
+            let pending_transaction = match ton_wallet
+                .send(&signed_message.message, signed_message.expire_at)
+                .await
+            {
+                Ok(pending_transaction) => pending_transaction,
+                Err(err) => {
+                    pending_messages.remove(&address, &message_hash).await?;
+                    return Err(err).handle_error();
+                },
+            };
+
+            serde_json::to_value(&pending_transaction).handle_error()
+        }
+
+        let mut ton_wallet = ton_wallet.write().await;
+
+        let result = internal_fn(&mut ton_wallet, pending_messages, signed_message)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_ton_wallet_restore_pending_messages(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    pending_messages: *mut c_void,
+    transport: *mut c_void,
+    transport_type: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+    let pending_messages = &*(pending_messages as *mut Arc<PendingMessagesStorage>);
+
+    let transport_type = transport_type.to_string_from_ptr();
+
+    let transport = match_transport(transport, &transport_type);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            ton_wallet: &mut TonWallet,
+            pending_messages: &PendingMessagesStorage,
+            transport: Arc<dyn Transport>,
+        ) -> Result<serde_json::Value, String> {
+            let restored =
+                restore_pending_messages(ton_wallet, pending_messages, transport.as_ref()).await?;
+
+            serde_json::to_value(&restored).handle_error()
+        }
+
+        let mut ton_wallet = ton_wallet.write().await;
+
+        let result = internal_fn(&mut ton_wallet, pending_messages, transport)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_generic_contract_send_persistent(
+    result_port: c_longlong,
+    generic_contract: *mut c_void,
+    pending_messages: *mut c_void,
+    signed_message: *mut c_char,
+) {
+    let generic_contract = &*(generic_contract as *mut RwLock<GenericContract>);
+    let pending_messages = &*(pending_messages as *mut Arc<PendingMessagesStorage>);
+
+    let signed_message = signed_message.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            generic_contract: &mut GenericContract,
+            pending_messages: &PendingMessagesStorage,
+            signed_message: String,
+        ) -> Result<serde_json::Value, String> {
+            let signed_message =
+                serde_json::from_str::<SignedMessage>(&signed_message).handle_error()?;
+
+            let address = generic_contract.address().to_string();
+            let pending_message =
+                make_pending_message(&signed_message, generic_contract.latest_lt())?;
+            let message_hash = pending_message.message_hash.clone();
+
+            pending_messages.add(&address, pending_message).await?;
+
+            let pending_transaction = match generic_contract
+                .send(&signed_message.message, signed_message.expire_at)
+                .await
+            {
+                Ok(pending_transaction) => pending_transaction,
+                Err(err) => {
+                    pending_messages.remove(&address, &message_hash).await?;
+                    return Err(err).handle_error();
+                },
+            };
+
+            serde_json::to_value(&pending_transaction).handle_error()
+        }
+
+        let mut generic_contract = generic_contract.write().await;
+
+        let result = internal_fn(&mut generic_contract, pending_messages, signed_message)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_generic_contract_restore_pending_messages(
+    result_port: c_longlong,
+    generic_contract: *mut c_void,
+    pending_messages: *mut c_void,
+    transport: *mut c_void,
+    transport_type: *mut c_char,
+) {
+    let generic_contract = &*(generic_contract as *mut RwLock<GenericContract>);
+    let pending_messages = &*(pending_messages as *mut Arc<PendingMessagesStorage>);
+
+    let transport_type = transport_type.to_string_from_ptr();
+
+    let transport = match_transport(transport, &transport_type);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            generic_contract: &mut GenericContract,
+            pending_messages: &PendingMessagesStorage,
+            transport: Arc<dyn Transport>,
+        ) -> Result<serde_json::Value, String> {
+            let restored =
+                restore_pending_messages(generic_contract, pending_messages, transport.as_ref())
+                    .await?;
+
+            serde_json::to_value(&restored).handle_error()
+        }
+
+        let mut generic_contract = generic_contract.write().await;
+
+        let result = internal_fn(&mut generic_contract, pending_messages, transport)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_pending_messages_storage_free_ptr(ptr: *mut c_void) {
+    println!("nt_pending_messages_storage_free_ptr");
+    Box::from_raw(ptr as *mut Arc<PendingMessagesStorage>);
+}
+
+fn make_pending_message(
+    signed_message: &SignedMessage,
+    latest_lt: u64,
+) -> Result<PendingMessage, String> {
+    let cell = signed_message.message.serialize().handle_error()?;
+
+    let boc = ton_types::serialize_toc(&cell)
+        .map(base64::encode)
+        .handle_error()?;
+
+    Ok(PendingMessage {
+        message_hash: cell.repr_hash().to_hex_string(),
+        expire_at: signed_message.expire_at,
+        boc,
+        latest_lt,
+    })
+}
+
+pub(crate) fn forget_pending_message(
+    pending_messages: &Option<Arc<PendingMessagesStorage>>,
+    pending_transaction: &PendingTransaction,
+) {
+    if let Some(pending_messages) = pending_messages.clone() {
+        let message_hash = pending_transaction.message_hash.to_hex_string();
+
+        // An entry which wasn't removed is dropped on the next restore
+        // since its message is already delivered or expired
+        runtime!().spawn(async move {
+            pending_messages.remove_message(&message_hash).await.ok();
+        });
+    }
+}
+
+#[async_trait]
+pub(crate) trait PendingMessagesSender: Send {
+    fn sender_address(&self) -> &MsgAddressInt;
+
+    fn pending_hashes(&self) -> Vec<String>;
+
+    fn latest_lt(&self) -> u64;
+
+    async fn send_message(
+        &mut self,
+        message: &ton_block::Message,
+        expire_at: u32,
+    ) -> Result<PendingTransaction, String>;
+}
+
+#[async_trait]
+impl PendingMessagesSender for TonWallet {
+    fn sender_address(&self) -> &MsgAddressInt {
+        self.address()
+    }
+
+    fn pending_hashes(&self) -> Vec<String> {
+        self.pending_transactions()
+            .iter()
+            .map(|e| e.message_hash.to_hex_string())
+            .collect()
+    }
+
+    fn latest_lt(&self) -> u64 {
+        self.contract_state()
+            .last_transaction_id
+            .map(|e| e.lt())
+            .unwrap_or_default()
+    }
+
+    async fn send_message(
+        &mut self,
+        message: &ton_block::Message,
+        expire_at: u32,
+    ) -> Result<PendingTransaction, String> {
+        self.send(message, expire_at).await.handle_error()
+    }
+}
+
+#[async_trait]
+impl PendingMessagesSender for GenericContract {
+    fn sender_address(&self) -> &MsgAddressInt {
+        self.address()
+    }
+
+    fn pending_hashes(&self) -> Vec<String> {
+        self.pending_transactions()
+            .iter()
+            .map(|e| e.message_hash.to_hex_string())
+            .collect()
+    }
+
+    fn latest_lt(&self) -> u64 {
+        self.contract_state()
+            .last_transaction_id
+            .map(|e| e.lt())
+            .unwrap_or_default()
+    }
+
+    async fn send_message(
+        &mut self,
+        message: &ton_block::Message,
+        expire_at: u32,
+    ) -> Result<PendingTransaction, String> {
+        self.send(message, expire_at).await.handle_error()
+    }
+}
+
+// Delivered and expired messages are removed from the storage, messages which
+// couldn't be resent are kept there to be retried on the next restore
+pub(crate) async fn restore_pending_messages<T>(
+    sender: &mut T,
+    pending_messages: &PendingMessagesStorage,
+    transport: &dyn Transport,
+) -> Result<RestoredPendingMessages, String>
+where
+    T: PendingMessagesSender,
+{
+    let address = sender.sender_address().clone();
+
+    let mut restored = RestoredPendingMessages::default();
+
+    let messages = pending_messages.get(&address.to_string()).await;
+
+    if messages.is_empty() {
+        return Ok(restored);
+    }
+
+    let mut delivered = find_delivered_transactions(transport, &address, &messages).await?;
+
+    let now = clock!().now_sec_u64() as u32;
+
+    let pending_hashes = sender.pending_hashes();
+
+    let mut alive = Vec::new();
+
+    for message in messages {
+        match delivered.remove(&message.message_hash) {
+            Some(raw_transaction) => {
+                // A transaction which can't be parsed still proves that the message was
+                // delivered
+                let transaction =
+                    Transaction::try_from((raw_transaction.hash, raw_transaction.data)).ok();
+
+                restored.delivered.push(DeliveredPendingMessage {
+                    pending_transaction: message.pending_transaction()?,
+                    transaction,
+                });
+            },
+            None if message.expire_at <= now => {
+                restored.expired.push(message.pending_transaction()?)
+            },
+            None if pending_hashes.contains(&message.message_hash) => {
+                alive.push(message.message_hash)
+            },
+            None => {
+                let result = match ton_block::Message::construct_from_base64(&message.boc) {
+                    Ok(raw_message) => sender.send_message(&raw_message, message.expire_at).await,
+                    Err(err) => Err(err).handle_error(),
+                };
+
+                match result {
+                    Ok(pending_transaction) => restored.resent.push(pending_transaction),
+                    Err(error) => restored.failed.push(FailedPendingMessage {
+                        message: message.clone(),
+                        error,
+                    }),
+                }
+
+                alive.push(message.message_hash);
+            },
+        }
+    }
+
+    pending_messages
+        .retain(&address.to_string(), |e| alive.contains(&e.message_hash))
+        .await?;
+
+    Ok(restored)
+}
+
+// Pages back through the sender's transactions until every message is found or
+// the page reaches the oldest logical time any of the messages could have been
+// sent at
+async fn find_delivered_transactions(
+    transport: &dyn Transport,
+    address: &MsgAddressInt,
+    messages: &[PendingMessage],
+) -> Result<HashMap<String, RawTransaction>, String> {
+    let mut delivered = HashMap::new();
+
+    let latest_lt = messages
+        .iter()
+        .map(|e| e.latest_lt)
+        .min()
+        .unwrap_or_default();
+
+    let mut from_lt = u64::MAX;
+
+    loop {
+        let raw_transactions = transport
+            .get_transactions(address, from_lt, RESTORE_PAGE_SIZE)
+            .await
+            .handle_error()?;
+
+        let is_last_page = raw_transactions.len() < RESTORE_PAGE_SIZE as usize;
+
+        let mut prev_trans_lt = 0;
+
+        for raw_transaction in raw_transactions {
+            if raw_transaction.data.lt <= latest_lt {
+                return Ok(delivered);
+            }
+
+            prev_trans_lt = raw_transaction.data.prev_trans_lt;
+
+            let message_hash = match raw_transaction.data.in_msg_cell() {
+                Some(cell) => cell.repr_hash().to_hex_string(),
+                None => continue,
+            };
+
+            if messages.iter().any(|e| e.message_hash == message_hash) {
+                delivered.insert(message_hash, raw_transaction);
+            }
+        }
+
+        if is_last_page || delivered.len() == messages.len() || prev_trans_lt <= latest_lt {
+            return Ok(delivered);
+        }
+
+        from_lt = prev_trans_lt;
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use nekoton::core::models::{PendingTransaction, Transaction};
+use nekoton_utils::serde_u64;
+use serde::{Deserialize, Serialize};
+
+use crate::HandleError;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PendingMessagesData {
+    pub version: u32,
+    pub messages: HashMap<String, Vec<PendingMessage>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingMessage {
+    pub message_hash: String,
+    pub expire_at: u32,
+    pub boc: String,
+    // Logical time of the sender's last transaction when the message was sent
+    #[serde(with = "serde_u64", default)]
+    pub latest_lt: u64,
+}
+
+impl PendingMessage {
+    pub fn pending_transaction(&self) -> Result<PendingTransaction, String> {
+        serde_json::from_value(serde_json::json!({
+            "messageHash": self.message_hash,
+            "src": null,
+            "expireAt": self.expire_at,
+        }))
+        .handle_error()
+    }
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredPendingMessages {
+    pub resent: Vec<PendingTransaction>,
+    pub delivered: Vec<DeliveredPendingMessage>,
+    pub expired: Vec<PendingTransaction>,
+    pub failed: Vec<FailedPendingMessage>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveredPendingMessage {
+    pub pending_transaction: PendingTransaction,
+    pub transaction: Option<Transaction>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedPendingMessage {
+    pub message: PendingMessage,
+    pub error: String,
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use nekoton::external::Storage;
+use tokio::sync::RwLock;
+
+use crate::{
+    core::pending_messages::models::{PendingMessage, PendingMessagesData},
+    HandleError,
+};
+
+const STORAGE_KEY: &str = "__nekoton_flutter__pending_messages";
+const CURRENT_VERSION: u32 = 1;
+
+pub struct PendingMessagesStorage {
+    storage: Arc<dyn Storage>,
+    data: RwLock<PendingMessagesData>,
+}
+
+impl PendingMessagesStorage {
+    pub async fn load(storage: Arc<dyn Storage>) -> Result<Self, String> {
+        let data = load_data(storage.as_ref()).await?;
+
+        Ok(Self {
+            storage,
+            data: RwLock::new(data),
+        })
+    }
+
+    pub async fn get(&self, address: &str) -> Vec<PendingMessage> {
+        self.data
+            .read()
+            .await
+            .messages
+            .get(address)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub async fn add(&self, address: &str, message: PendingMessage) -> Result<(), String> {
+        let mut data = self.data.write().await;
+
+        let messages = data.messages.entry(address.to_owned()).or_default();
+
+        messages.retain(|e| e.message_hash != message.message_hash);
+        messages.push(message);
+
+        self.save(&data).await
+    }
+
+    pub async fn remove(&self, address: &str, message_hash: &str) -> Result<(), String> {
+        self.retain(address, |e| e.message_hash != message_hash)
+            .await
+    }
+
+    pub async fn remove_message(&self, message_hash: &str) -> Result<(), String> {
+        let mut data = self.data.write().await;
+
+        let len = data.messages.values().map(Vec::len).sum::<usize>();
+
+        data.messages.retain(|_, messages| {
+            messages.retain(|e| e.message_hash != message_hash);
+            !messages.is_empty()
+        });
+
+        if data.messages.values().map(Vec::len).sum::<usize>() == len {
+            return Ok(());
+        }
+
+        self.save(&data).await
+    }
+
+    pub async fn retain<F>(&self, address: &str, f: F) -> Result<(), String>
+    where
+        F: FnMut(&PendingMessage) -> bool,
+    {
+        let mut data = self.data.write().await;
+
+        let messages = match data.messages.get_mut(address) {
+            Some(messages) => messages,
+            None => return Ok(()),
+        };
+
+        let len = messages.len();
+        messages.retain(f);
+
+        if messages.len() == len {
+            return Ok(());
+        }
+
+        if messages.is_empty() {
+            data.messages.remove(address);
+        }
+
+        self.save(&data).await
+    }
+
+    pub async fn clear(&self) -> Result<(), String> {
+        let mut data = self.data.write().await;
+
+        *data = PendingMessagesData::default();
+
+        self.storage.remove(STORAGE_KEY).await.handle_error()
+    }
+
+    async fn save(&self, data: &PendingMessagesData) -> Result<(), String> {
+        let data = serde_json::to_string(&PendingMessagesData {
+            version: CURRENT_VERSION,
+            ..data.clone()
+        })
+        .handle_error()?;
+
+        self.storage.set(STORAGE_KEY, &data).await.handle_error()
+    }
+}
+
+async fn load_data(storage: &dyn Storage) -> Result<PendingMessagesData, String> {
+    let data = match storage.get(STORAGE_KEY).await.handle_error()? {
+        Some(data) => serde_json::from_str::<PendingMessagesData>(&data).handle_error()?,
+        None => PendingMessagesData::default(),
+    };
+
+    if data.version > CURRENT_VERSION {
+        return Err("Unsupported pending messages version").handle_error();
+    }
+
+    Ok(data)
+}
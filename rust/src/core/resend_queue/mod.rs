@@ -0,0 +1,434 @@
+mod models;
+
+use std::{
+    collections::HashMap,
+    os::raw::{c_char, c_longlong, c_void},
+    sync::{Arc, Mutex, Weak},
+};
+
+use allo_isolate::Isolate;
+use lazy_static::lazy_static;
+use nekoton::{
+    core::{keystore::KeyStore, models::PendingTransaction, ton_wallet::TonWallet},
+    crypto::UnsignedMessage,
+};
+use nekoton_utils::Clock;
+use secstr::SecUtf8;
+use tokio::sync::RwLock;
+
+use crate::{
+    clock,
+    core::{
+        keystore::{sign, KeyStoreImpl},
+        resend_queue::models::{ResendEvent, ResendPolicy},
+    },
+    runtime, HandleError, MatchResult, PostWithResult, ToPtrAddress, ToSecretStringFromPtr,
+    ToStringFromPtr, CLOCK, RUNTIME,
+};
+
+const MAX_RESEND_ATTEMPTS: u8 = 10;
+const RESEND_GRACE_PERIOD: u32 = 600;
+
+lazy_static! {
+    static ref RESEND_QUEUES: Mutex<HashMap<String, Weak<ResendQueue>>> =
+        Mutex::new(HashMap::new());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_resend_queue_create(on_event_port: c_longlong) -> *mut c_char {
+    fn internal_fn(on_event_port: i64) -> Result<serde_json::Value, String> {
+        let resend_queue = ResendQueue {
+            on_event_port: Isolate::new(on_event_port),
+            entries: Mutex::new(HashMap::new()),
+        };
+
+        let ptr = Box::into_raw(Box::new(Arc::new(resend_queue)));
+
+        serde_json::to_value(ptr.to_ptr_address()).handle_error()
+    }
+
+    internal_fn(on_event_port).match_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_ton_wallet_send_with_resend(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    resend_queue: *mut c_void,
+    keystore: *mut c_void,
+    unsigned_message: *mut c_void,
+    signer: *mut c_char,
+    input: *mut c_char,
+    policy: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+    let resend_queue = &*(resend_queue as *mut Arc<ResendQueue>);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+    let unsigned_message = &*(unsigned_message as *mut RwLock<Box<dyn UnsignedMessage>>);
+
+    let signer = signer.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
+    let policy = policy.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            ton_wallet: &mut TonWallet,
+            resend_queue: &Arc<ResendQueue>,
+            keystore: &KeyStore,
+            unsigned_message: Box<dyn UnsignedMessage>,
+            signer: String,
+            input: SecUtf8,
+            policy: String,
+        ) -> Result<serde_json::Value, String> {
+            let policy = serde_json::from_str::<ResendPolicy>(&policy).handle_error()?;
+
+            if policy.max_attempts > MAX_RESEND_ATTEMPTS {
+                return Err("Too many resend attempts").handle_error();
+            }
+
+            let pending_transaction = send(
+                ton_wallet,
+                keystore,
+                unsigned_message.as_ref(),
+                &signer,
+                input,
+            )
+            .await?;
+
+            resend_queue.insert(
+                &pending_transaction,
+                ResendEntry {
+                    unsigned_message,
+                    signer,
+                    policy,
+                    attempt: 0,
+                    expired_at: None,
+                },
+            );
+
+            serde_json::to_value(&pending_transaction).handle_error()
+        }
+
+        let unsigned_message = unsigned_message.read().await.clone();
+
+        let mut ton_wallet = ton_wallet.write().await;
+
+        let result = internal_fn(
+            &mut ton_wallet,
+            resend_queue,
+            keystore,
+            unsigned_message,
+            signer,
+            input,
+            policy,
+        )
+        .await
+        .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_ton_wallet_resend_expired(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    resend_queue: *mut c_void,
+    keystore: *mut c_void,
+    message_hash: *mut c_char,
+    input: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+    let resend_queue = &*(resend_queue as *mut Arc<ResendQueue>);
+    let keystore = &*(keystore as *mut KeyStoreImpl);
+
+    let message_hash = message_hash.to_string_from_ptr();
+    let input = input.to_secret_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            ton_wallet: &mut TonWallet,
+            resend_queue: &Arc<ResendQueue>,
+            keystore: &KeyStore,
+            message_hash: String,
+            input: SecUtf8,
+        ) -> Result<serde_json::Value, String> {
+            let is_pending = ton_wallet
+                .pending_transactions()
+                .iter()
+                .any(|e| e.message_hash.to_hex_string() == message_hash);
+
+            let (mut unsigned_message, signer, attempt) =
+                resend_queue.begin_resend(&message_hash, is_pending)?;
+
+            unsigned_message.refresh_timeout(clock!().as_ref());
+
+            let pending_transaction = match send(
+                ton_wallet,
+                keystore,
+                unsigned_message.as_ref(),
+                &signer,
+                input,
+            )
+            .await
+            {
+                Ok(pending_transaction) => pending_transaction,
+                Err(error) => {
+                    resend_queue.on_event(ResendEvent::Failed {
+                        message_hash,
+                        attempt,
+                        error: error.clone(),
+                    });
+
+                    return Err(error);
+                },
+            };
+
+            resend_queue.replace(&message_hash, &pending_transaction, unsigned_message);
+
+            resend_queue.on_event(ResendEvent::Resent {
+                previous_message_hash: message_hash,
+                attempt,
+                pending_transaction: pending_transaction.clone(),
+            });
+
+            serde_json::to_value(&pending_transaction).handle_error()
+        }
+
+        let mut ton_wallet = ton_wallet.write().await;
+
+        let result = internal_fn(&mut ton_wallet, resend_queue, keystore, message_hash, input)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_resend_queue_cancel(
+    result_port: c_longlong,
+    resend_queue: *mut c_void,
+    message_hash: *mut c_char,
+) {
+    let resend_queue = &*(resend_queue as *mut Arc<ResendQueue>);
+
+    let message_hash = message_hash.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            resend_queue: &ResendQueue,
+            message_hash: String,
+        ) -> Result<serde_json::Value, String> {
+            let cancelled = resend_queue.remove(&message_hash).is_some();
+
+            serde_json::to_value(cancelled).handle_error()
+        }
+
+        let result = internal_fn(resend_queue, message_hash).await.match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_resend_queue_free_ptr(ptr: *mut c_void) {
+    println!("nt_resend_queue_free_ptr");
+    Box::from_raw(ptr as *mut Arc<ResendQueue>);
+}
+
+pub fn mark_message_delivered(pending_transaction: &PendingTransaction) {
+    let message_hash = pending_transaction.message_hash.to_hex_string();
+
+    if let Some(resend_queue) = find_queue(&message_hash) {
+        resend_queue.remove(&message_hash);
+    }
+}
+
+pub fn mark_message_expired(pending_transaction: &PendingTransaction) {
+    let message_hash = pending_transaction.message_hash.to_hex_string();
+
+    if let Some(resend_queue) = find_queue(&message_hash) {
+        resend_queue.on_message_expired(message_hash);
+    }
+}
+
+struct ResendQueue {
+    on_event_port: Isolate,
+    entries: Mutex<HashMap<String, ResendEntry>>,
+}
+
+impl ResendQueue {
+    fn insert(self: &Arc<Self>, pending_transaction: &PendingTransaction, entry: ResendEntry) {
+        let message_hash = pending_transaction.message_hash.to_hex_string();
+
+        self.prune();
+
+        RESEND_QUEUES
+            .lock()
+            .unwrap()
+            .insert(message_hash.clone(), Arc::downgrade(self));
+
+        self.entries.lock().unwrap().insert(message_hash, entry);
+    }
+
+    fn remove(&self, message_hash: &str) -> Option<ResendEntry> {
+        RESEND_QUEUES.lock().unwrap().remove(message_hash);
+
+        self.entries.lock().unwrap().remove(message_hash)
+    }
+
+    fn on_message_expired(&self, message_hash: String) {
+        let now = clock!().now_sec_u64() as u32;
+
+        let attempt = match self.entries.lock().unwrap().get_mut(&message_hash) {
+            Some(entry) => {
+                entry.expired_at = Some(now);
+                entry.attempt
+            },
+            None => return,
+        };
+
+        self.on_event(ResendEvent::Expired {
+            message_hash,
+            attempt,
+        });
+    }
+
+    // Only messages reported as expired by the wallet subscription can be resent,
+    // otherwise the original message might still be delivered
+    fn begin_resend(
+        &self,
+        message_hash: &str,
+        is_pending: bool,
+    ) -> Result<(Box<dyn UnsignedMessage>, String, u8), String> {
+        self.prune();
+
+        let mut entries = self.entries.lock().unwrap();
+
+        let entry = entries
+            .get_mut(message_hash)
+            .ok_or("Message is not in the resend queue")
+            .handle_error()?;
+
+        if entry.expired_at.is_none() || is_pending {
+            return Err("Message is not expired yet").handle_error();
+        }
+
+        if entry.attempt >= entry.policy.max_attempts {
+            let attempts = entry.attempt;
+
+            entries.remove(message_hash);
+            drop(entries);
+
+            RESEND_QUEUES.lock().unwrap().remove(message_hash);
+
+            self.on_event(ResendEvent::Exhausted {
+                message_hash: message_hash.to_owned(),
+                attempts,
+            });
+
+            return Err("Resend attempts exhausted").handle_error();
+        }
+
+        entry.attempt += 1;
+
+        Ok((
+            entry.unsigned_message.clone(),
+            entry.signer.clone(),
+            entry.attempt,
+        ))
+    }
+
+    fn replace(
+        self: &Arc<Self>,
+        message_hash: &str,
+        pending_transaction: &PendingTransaction,
+        unsigned_message: Box<dyn UnsignedMessage>,
+    ) {
+        if let Some(entry) = self.remove(message_hash) {
+            self.insert(
+                pending_transaction,
+                ResendEntry {
+                    unsigned_message,
+                    expired_at: None,
+                    ..entry
+                },
+            );
+        }
+    }
+
+    fn prune(&self) {
+        let now = clock!().now_sec_u64() as u32;
+
+        let mut entries = self.entries.lock().unwrap();
+
+        let stale = entries
+            .iter()
+            .filter(|(_, entry)| {
+                matches!(entry.expired_at, Some(expired_at) if expired_at.saturating_add(RESEND_GRACE_PERIOD) <= now)
+            })
+            .map(|(message_hash, _)| message_hash.clone())
+            .collect::<Vec<_>>();
+
+        if stale.is_empty() {
+            return;
+        }
+
+        let mut resend_queues = RESEND_QUEUES.lock().unwrap();
+
+        for message_hash in stale {
+            entries.remove(&message_hash);
+            resend_queues.remove(&message_hash);
+        }
+    }
+
+    fn on_event(&self, event: ResendEvent) {
+        let payload = serde_json::to_string(&event).unwrap();
+
+        self.on_event_port.post(payload);
+    }
+}
+
+struct ResendEntry {
+    unsigned_message: Box<dyn UnsignedMessage>,
+    signer: String,
+    policy: ResendPolicy,
+    attempt: u8,
+    expired_at: Option<u32>,
+}
+
+async fn send(
+    ton_wallet: &mut TonWallet,
+    keystore: &KeyStore,
+    unsigned_message: &dyn UnsignedMessage,
+    signer: &str,
+    input: SecUtf8,
+) -> Result<PendingTransaction, String> {
+    let signature = sign(keystore, signer.to_owned(), unsigned_message.hash(), input).await?;
+
+    let signed_message = unsigned_message.sign(&signature).handle_error()?;
+
+    ton_wallet
+        .send(&signed_message.message, signed_message.expire_at)
+        .await
+        .handle_error()
+}
+
+fn find_queue(message_hash: &str) -> Option<Arc<ResendQueue>> {
+    let mut resend_queues = RESEND_QUEUES.lock().unwrap();
+
+    let resend_queue = resend_queues.get(message_hash)?.upgrade();
+
+    if resend_queue.is_none() {
+        resend_queues.remove(message_hash);
+    }
+
+    resend_queue
+}
@@ -0,0 +1,29 @@
+use nekoton::core::models::PendingTransaction;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResendPolicy {
+    pub max_attempts: u8,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum ResendEvent {
+    #[serde(rename_all = "camelCase")]
+    Expired { message_hash: String, attempt: u8 },
+    #[serde(rename_all = "camelCase")]
+    Resent {
+        previous_message_hash: String,
+        attempt: u8,
+        pending_transaction: PendingTransaction,
+    },
+    #[serde(rename_all = "camelCase")]
+    Failed {
+        message_hash: String,
+        attempt: u8,
+        error: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Exhausted { message_hash: String, attempts: u8 },
+}
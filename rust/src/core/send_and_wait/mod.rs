@@ -0,0 +1,371 @@
+mod models;
+pub(crate) mod waiters;
+
+use std::{
+    collections::VecDeque,
+    os::raw::{c_char, c_longlong, c_uint, c_void},
+    sync::Arc,
+    time::Duration,
+};
+
+use allo_isolate::Isolate;
+use nekoton::{
+    core::{
+        generic_contract::GenericContract,
+        models::{PendingTransaction, Transaction},
+        ton_wallet::TonWallet,
+    },
+    crypto::SignedMessage,
+    transport::{models::RawTransaction, Transport},
+};
+use nekoton_utils::Clock;
+use tokio::{
+    sync::{oneshot, RwLock},
+    time::Instant,
+};
+use ton_block::{MsgAddressInt, Serializable};
+use ton_types::UInt256;
+
+use crate::{
+    clock,
+    core::send_and_wait::{
+        models::{SendAndWaitResult, SentMessageStatus},
+        waiters::{register_waiter, remove_waiter, MessageOutcome},
+    },
+    runtime,
+    transport::match_transport,
+    HandleError, MatchResult, PostWithResult, ToPtrAddress, ToStringFromPtr, CLOCK, RUNTIME,
+};
+
+const CHAIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_CHAIN_TIMEOUT: Duration = Duration::from_secs(60);
+const EXPIRATION_MARGIN: Duration = Duration::from_secs(60);
+const CHAIN_PAGE_SIZE: u8 = 16;
+const MAX_CHAIN_TRANSACTIONS: usize = 100;
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_ton_wallet_send_and_wait(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    transport: *mut c_void,
+    transport_type: *mut c_char,
+    signed_message: *mut c_char,
+    wait_for_chain: c_uint,
+    timeout: c_uint,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+
+    let transport_type = transport_type.to_string_from_ptr();
+    let signed_message = signed_message.to_string_from_ptr();
+    let wait_for_chain = wait_for_chain != 0;
+
+    let transport = match_transport(transport, &transport_type);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            ton_wallet: &RwLock<TonWallet>,
+            transport: Arc<dyn Transport>,
+            signed_message: String,
+            wait_for_chain: bool,
+            timeout: u32,
+        ) -> Result<serde_json::Value, String> {
+            let deadline = make_deadline(timeout);
+
+            let signed_message =
+                serde_json::from_str::<SignedMessage>(&signed_message).handle_error()?;
+
+            let (pending_transaction, rx) = {
+                let mut ton_wallet = ton_wallet.write().await;
+
+                let pending_transaction = ton_wallet
+                    .send(&signed_message.message, signed_message.expire_at)
+                    .await
+                    .handle_error()?;
+
+                let rx = register_waiter(&pending_transaction.message_hash);
+
+                (pending_transaction, rx)
+            };
+
+            let result = wait_for_message(
+                transport.as_ref(),
+                pending_transaction,
+                rx,
+                wait_for_chain,
+                deadline,
+            )
+            .await?;
+
+            serde_json::to_value(&result).handle_error()
+        }
+
+        let result = internal_fn(
+            ton_wallet,
+            transport,
+            signed_message,
+            wait_for_chain,
+            timeout,
+        )
+        .await
+        .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_generic_contract_send_and_wait(
+    result_port: c_longlong,
+    generic_contract: *mut c_void,
+    transport: *mut c_void,
+    transport_type: *mut c_char,
+    signed_message: *mut c_char,
+    wait_for_chain: c_uint,
+    timeout: c_uint,
+) {
+    let generic_contract = &*(generic_contract as *mut RwLock<GenericContract>);
+
+    let transport_type = transport_type.to_string_from_ptr();
+    let signed_message = signed_message.to_string_from_ptr();
+    let wait_for_chain = wait_for_chain != 0;
+
+    let transport = match_transport(transport, &transport_type);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            generic_contract: &RwLock<GenericContract>,
+            transport: Arc<dyn Transport>,
+            signed_message: String,
+            wait_for_chain: bool,
+            timeout: u32,
+        ) -> Result<serde_json::Value, String> {
+            let deadline = make_deadline(timeout);
+
+            let signed_message =
+                serde_json::from_str::<SignedMessage>(&signed_message).handle_error()?;
+
+            let (pending_transaction, rx) = {
+                let mut generic_contract = generic_contract.write().await;
+
+                let pending_transaction = generic_contract
+                    .send(&signed_message.message, signed_message.expire_at)
+                    .await
+                    .handle_error()?;
+
+                let rx = register_waiter(&pending_transaction.message_hash);
+
+                (pending_transaction, rx)
+            };
+
+            let result = wait_for_message(
+                transport.as_ref(),
+                pending_transaction,
+                rx,
+                wait_for_chain,
+                deadline,
+            )
+            .await?;
+
+            serde_json::to_value(&result).handle_error()
+        }
+
+        let result = internal_fn(
+            generic_contract,
+            transport,
+            signed_message,
+            wait_for_chain,
+            timeout,
+        )
+        .await
+        .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+fn make_deadline(timeout: u32) -> Option<Instant> {
+    (timeout != 0).then(|| Instant::now() + Duration::from_secs(timeout as u64))
+}
+
+// Without an explicit timeout the wait still ends some time after the message
+// expires, so a waiter is never left behind if the subscription stops polling
+fn expiration_deadline(expire_at: u32) -> Instant {
+    let remaining = (expire_at as u64).saturating_sub(clock!().now_sec_u64());
+
+    Instant::now() + Duration::from_secs(remaining) + EXPIRATION_MARGIN
+}
+
+async fn wait_for_message(
+    transport: &dyn Transport,
+    pending_transaction: PendingTransaction,
+    rx: oneshot::Receiver<MessageOutcome>,
+    wait_for_chain: bool,
+    deadline: Option<Instant>,
+) -> Result<SendAndWaitResult, String> {
+    let mut result = SendAndWaitResult {
+        pending_transaction,
+        status: SentMessageStatus::Timeout,
+        transaction: None,
+        child_transactions: Vec::new(),
+        is_settled: false,
+    };
+
+    let message_deadline =
+        deadline.unwrap_or_else(|| expiration_deadline(result.pending_transaction.expire_at));
+
+    let outcome = match tokio::time::timeout_at(message_deadline, rx).await {
+        Ok(outcome) => outcome.handle_error()?,
+        Err(_) => {
+            remove_waiter(&result.pending_transaction.message_hash);
+            return Ok(result);
+        },
+    };
+
+    let transaction = match outcome {
+        MessageOutcome::Sent(transaction) => transaction,
+        MessageOutcome::Expired => {
+            result.status = SentMessageStatus::Expired;
+            result.is_settled = true;
+            return Ok(result);
+        },
+    };
+
+    result.status = SentMessageStatus::Sent;
+    result.is_settled = !wait_for_chain;
+
+    if wait_for_chain {
+        if let Some(transaction) = &transaction {
+            let deadline = deadline.unwrap_or_else(|| Instant::now() + DEFAULT_CHAIN_TIMEOUT);
+
+            let (child_transactions, is_settled) =
+                trace_chain(transport, &transaction.id.hash, deadline).await?;
+
+            result.child_transactions = child_transactions;
+            result.is_settled = is_settled;
+        }
+    }
+
+    result.transaction = transaction;
+
+    Ok(result)
+}
+
+async fn trace_chain(
+    transport: &dyn Transport,
+    transaction_hash: &UInt256,
+    deadline: Instant,
+) -> Result<(Vec<Transaction>, bool), String> {
+    let root = transport
+        .get_transaction(transaction_hash)
+        .await
+        .handle_error()?
+        .ok_or("Transaction not found")
+        .handle_error()?;
+
+    let mut queue = internal_out_messages(&root.data)?
+        .into_iter()
+        .collect::<VecDeque<_>>();
+
+    let mut child_transactions = Vec::new();
+
+    while let Some((dst, message_hash, created_lt)) = queue.pop_front() {
+        if child_transactions.len() >= MAX_CHAIN_TRANSACTIONS {
+            return Ok((child_transactions, false));
+        }
+
+        let raw_transaction = loop {
+            if let Some(raw_transaction) =
+                find_transaction(transport, &dst, &message_hash, created_lt).await?
+            {
+                break raw_transaction;
+            }
+
+            if Instant::now() + CHAIN_POLL_INTERVAL >= deadline {
+                return Ok((child_transactions, false));
+            }
+
+            tokio::time::sleep(CHAIN_POLL_INTERVAL).await;
+        };
+
+        queue.extend(internal_out_messages(&raw_transaction.data)?);
+
+        let transaction =
+            Transaction::try_from((raw_transaction.hash, raw_transaction.data)).handle_error()?;
+
+        child_transactions.push(transaction);
+    }
+
+    Ok((child_transactions, true))
+}
+
+// Pages back from the latest transaction until the one processing the message
+// is found or the page reaches the logical time the message was created at
+async fn find_transaction(
+    transport: &dyn Transport,
+    address: &MsgAddressInt,
+    message_hash: &UInt256,
+    created_lt: u64,
+) -> Result<Option<RawTransaction>, String> {
+    let mut from_lt = u64::MAX;
+
+    loop {
+        let raw_transactions = transport
+            .get_transactions(address, from_lt, CHAIN_PAGE_SIZE)
+            .await
+            .handle_error()?;
+
+        let is_last_page = raw_transactions.len() < CHAIN_PAGE_SIZE as usize;
+
+        let mut prev_trans_lt = 0;
+
+        for raw_transaction in raw_transactions {
+            if raw_transaction.data.lt <= created_lt {
+                return Ok(None);
+            }
+
+            let is_target = raw_transaction
+                .data
+                .in_msg_cell()
+                .map(|cell| cell.repr_hash() == *message_hash)
+                .unwrap_or_default();
+
+            if is_target {
+                return Ok(Some(raw_transaction));
+            }
+
+            prev_trans_lt = raw_transaction.data.prev_trans_lt;
+        }
+
+        if is_last_page || prev_trans_lt <= created_lt {
+            return Ok(None);
+        }
+
+        from_lt = prev_trans_lt;
+    }
+}
+
+fn internal_out_messages(
+    transaction: &ton_block::Transaction,
+) -> Result<Vec<(MsgAddressInt, UInt256, u64)>, String> {
+    let mut messages = Vec::new();
+
+    transaction
+        .iterate_out_msgs(|message| {
+            if let Some(header) = message.int_header() {
+                messages.push((
+                    header.dst.clone(),
+                    message.serialize()?.repr_hash(),
+                    header.created_lt,
+                ));
+            }
+
+            Ok(true)
+        })
+        .handle_error()?;
+
+    Ok(messages)
+}
@@ -0,0 +1,20 @@
+use nekoton::core::models::{PendingTransaction, Transaction};
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendAndWaitResult {
+    pub pending_transaction: PendingTransaction,
+    pub status: SentMessageStatus,
+    pub transaction: Option<Transaction>,
+    pub child_transactions: Vec<Transaction>,
+    pub is_settled: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SentMessageStatus {
+    Sent,
+    Expired,
+    Timeout,
+}
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use lazy_static::lazy_static;
+use nekoton::core::models::{PendingTransaction, Transaction};
+use tokio::sync::oneshot;
+use ton_types::UInt256;
+
+lazy_static! {
+    static ref MESSAGE_WAITERS: Mutex<HashMap<String, Vec<oneshot::Sender<MessageOutcome>>>> =
+        Mutex::new(HashMap::new());
+}
+
+#[derive(Clone)]
+pub enum MessageOutcome {
+    Sent(Option<Transaction>),
+    Expired,
+}
+
+pub fn register_waiter(message_hash: &UInt256) -> oneshot::Receiver<MessageOutcome> {
+    let (tx, rx) = oneshot::channel();
+
+    MESSAGE_WAITERS
+        .lock()
+        .unwrap()
+        .entry(message_hash.to_hex_string())
+        .or_default()
+        .push(tx);
+
+    rx
+}
+
+// Only drops waiters whose receivers are gone, other callers may still wait for
+// the same message
+pub fn remove_waiter(message_hash: &UInt256) {
+    let message_hash = message_hash.to_hex_string();
+
+    let mut waiters = MESSAGE_WAITERS.lock().unwrap();
+
+    if let Some(senders) = waiters.get_mut(&message_hash) {
+        senders.retain(|tx| !tx.is_closed());
+
+        if senders.is_empty() {
+            waiters.remove(&message_hash);
+        }
+    }
+}
+
+pub fn notify_message_sent(
+    pending_transaction: &PendingTransaction,
+    transaction: &Option<Transaction>,
+) {
+    notify(
+        &pending_transaction.message_hash,
+        MessageOutcome::Sent(transaction.clone()),
+    );
+}
+
+pub fn notify_message_expired(pending_transaction: &PendingTransaction) {
+    notify(&pending_transaction.message_hash, MessageOutcome::Expired);
+}
+
+fn notify(message_hash: &UInt256, outcome: MessageOutcome) {
+    let senders = MESSAGE_WAITERS
+        .lock()
+        .unwrap()
+        .remove(&message_hash.to_hex_string())
+        .unwrap_or_default();
+
+    for tx in senders {
+        tx.send(outcome.clone()).ok();
+    }
+}
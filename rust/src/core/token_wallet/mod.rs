@@ -18,12 +18,13 @@ use nekoton::{
     },
     transport::Transport,
 };
-use nekoton_abi::{create_boc_or_comment_payload, num_bigint::BigUint};
+use nekoton_abi::num_bigint::BigUint;
 use tokio::sync::RwLock;
 use ton_block::{Block, Deserializable};
 
 use crate::{
-    clock, core::token_wallet::handler::TokenWalletSubscriptionHandlerImpl, parse_address, runtime,
+    clock, core::token_wallet::handler::TokenWalletSubscriptionHandlerImpl,
+    crypto::encrypted_comment::create_token_transfer_payload, parse_address, runtime,
     transport::match_transport, HandleError, MatchResult, PostWithResult, ToOptionalStringFromPtr,
     ToPtrAddress, ToStringFromPtr, CLOCK, RUNTIME,
 };
@@ -259,9 +260,7 @@ pub unsafe extern "C" fn nt_token_wallet_prepare_transfer(
             let tokens = BigUint::from_str(&tokens).handle_error()?;
 
             let payload = match payload {
-                Some(payload) => create_boc_or_comment_payload(&payload)
-                    .handle_error()?
-                    .into_cell(),
+                Some(payload) => create_token_transfer_payload(&payload)?,
                 None => ton_types::Cell::default(),
             };
 
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use allo_isolate::Isolate;
 use async_trait::async_trait;
 use nekoton::core::{
@@ -8,9 +10,17 @@ use nekoton::core::{
     ton_wallet::TonWalletSubscriptionHandler,
 };
 
-use crate::core::models::{
-    OnMessageExpiredPayload, OnMessageSentPayload, OnStateChangedPayload,
-    OnTransactionsFoundPayload,
+use crate::core::{
+    models::{
+        OnMessageExpiredPayload, OnMessageSentPayload, OnStateChangedPayload,
+        OnTransactionsFoundPayload,
+    },
+    multisig::observer::notify_transactions_found,
+    pending_messages::{
+        forget_pending_message, models::RestoredPendingMessages, storage::PendingMessagesStorage,
+    },
+    resend_queue::{mark_message_delivered, mark_message_expired},
+    send_and_wait::waiters::{notify_message_expired, notify_message_sent},
 };
 
 pub struct TonWalletSubscriptionHandlerImpl {
@@ -18,6 +28,7 @@ pub struct TonWalletSubscriptionHandlerImpl {
     on_message_expired_port: Isolate,
     on_state_changed_port: Isolate,
     on_transactions_found_port: Isolate,
+    pending_messages: Option<Arc<PendingMessagesStorage>>,
 }
 
 impl TonWalletSubscriptionHandlerImpl {
@@ -26,12 +37,24 @@ impl TonWalletSubscriptionHandlerImpl {
         on_message_expired_port: i64,
         on_state_changed_port: i64,
         on_transactions_found_port: i64,
+        pending_messages: Option<Arc<PendingMessagesStorage>>,
     ) -> Self {
         Self {
             on_message_sent_port: Isolate::new(on_message_sent_port),
             on_message_expired_port: Isolate::new(on_message_expired_port),
             on_state_changed_port: Isolate::new(on_state_changed_port),
             on_transactions_found_port: Isolate::new(on_transactions_found_port),
+            pending_messages,
+        }
+    }
+
+    pub fn on_pending_messages_restored(&self, restored: RestoredPendingMessages) {
+        for delivered in restored.delivered {
+            self.on_message_sent(delivered.pending_transaction, delivered.transaction);
+        }
+
+        for pending_transaction in restored.expired {
+            self.on_message_expired(pending_transaction);
         }
     }
 }
@@ -43,6 +66,10 @@ impl TonWalletSubscriptionHandler for TonWalletSubscriptionHandlerImpl {
         pending_transaction: PendingTransaction,
         transaction: Option<Transaction>,
     ) {
+        notify_message_sent(&pending_transaction, &transaction);
+        mark_message_delivered(&pending_transaction);
+        forget_pending_message(&self.pending_messages, &pending_transaction);
+
         let payload = serde_json::to_string(&OnMessageSentPayload {
             pending_transaction,
             transaction,
@@ -53,6 +80,10 @@ impl TonWalletSubscriptionHandler for TonWalletSubscriptionHandlerImpl {
     }
 
     fn on_message_expired(&self, pending_transaction: PendingTransaction) {
+        notify_message_expired(&pending_transaction);
+        mark_message_expired(&pending_transaction);
+        forget_pending_message(&self.pending_messages, &pending_transaction);
+
         let payload = serde_json::to_string(&OnMessageExpiredPayload {
             pending_transaction,
         })
@@ -72,6 +103,8 @@ impl TonWalletSubscriptionHandler for TonWalletSubscriptionHandlerImpl {
         transactions: Vec<TransactionWithData<TransactionAdditionalInfo>>,
         batch_info: TransactionsBatchInfo,
     ) {
+        notify_transactions_found(&transactions, &batch_info);
+
         let payload = serde_json::to_string(&OnTransactionsFoundPayload {
             transactions,
             batch_info,
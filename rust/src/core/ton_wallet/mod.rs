@@ -7,33 +7,56 @@ use std::{
 };
 
 use allo_isolate::Isolate;
+use bip39::Language;
 use nekoton::{
     core::{
-        models::{Expiration, MessageFlags},
+        keystore::KeyStore,
+        models::Expiration,
         ton_wallet::{
             extract_wallet_init_data, find_existing_wallets, get_wallet_custodians,
-            ExistingWalletInfo, Gift, TonWallet, TransferAction,
+            highload_wallet_v2, wallet_v3, ExistingWalletInfo, Gift, TonWallet, TransferAction,
+            WalletType,
         },
     },
-    crypto::SignedMessage,
+    crypto::{derive_from_phrase, DerivedKeySigner, MnemonicType, SignedMessage},
     transport::Transport,
 };
-use nekoton_abi::create_boc_or_comment_payload;
+use nekoton_abi::{create_boc_payload, create_comment_payload};
+use secstr::SecUtf8;
 use tokio::sync::RwLock;
-use ton_block::{Block, Deserializable};
+use ton_block::{Block, Deserializable, StateInit};
 
 use crate::{
     clock,
-    core::ton_wallet::{
-        handler::TonWalletSubscriptionHandlerImpl,
-        models::{ExistingWalletInfoHelper, WalletTypeHelper},
+    core::{
+        keystore::KeyStoreImpl,
+        pending_messages::{restore_pending_messages, storage::PendingMessagesStorage},
+        ton_wallet::{
+            handler::TonWalletSubscriptionHandlerImpl,
+            models::{
+                ExistingWalletInfoHelper, MessageFlagsHelper, TransferBody, TransferRecipient,
+                WalletDiscoveryParams, WalletDiscoverySource, WalletTypeHelper,
+            },
+        },
+    },
+    crypto::{
+        derived_key::{
+            derive_keypair, labs_derivation_path, models::DerivationScheme, phrase_to_seed,
+        },
+        encrypted_comment::create_encrypted_comment_payload,
+        mnemonic::parse_language,
     },
     parse_address, parse_public_key, runtime,
     transport::{match_transport, models::RawContractStateHelper},
     HandleError, MatchResult, PostWithResult, ToOptionalStringFromPtr, ToPtrAddress,
-    ToStringFromPtr, CLOCK, RUNTIME,
+    ToSecretStringFromPtr, ToStringFromPtr, CLOCK, RUNTIME,
 };
 
+const MULTISIG_MAX_MESSAGES: usize = 1;
+const WALLET_V3_MAX_MESSAGES: usize = 4;
+const HIGHLOAD_WALLET_V2_MAX_MESSAGES: usize = 100;
+const MAX_DISCOVERY_GAP_LIMIT: u32 = 50;
+
 #[no_mangle]
 pub unsafe extern "C" fn nt_ton_wallet_subscribe(
     result_port: c_longlong,
@@ -43,6 +66,7 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe(
     on_transactions_found_port: c_longlong,
     transport: *mut c_void,
     transport_type: *mut c_char,
+    pending_messages: *mut c_void,
     workchain: c_schar,
     public_key: *mut c_char,
     contract: *mut c_char,
@@ -51,6 +75,12 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe(
     let public_key = public_key.to_string_from_ptr();
     let contract = contract.to_string_from_ptr();
 
+    let pending_messages = if !pending_messages.is_null() {
+        Some((&*(pending_messages as *mut Arc<PendingMessagesStorage>)).clone())
+    } else {
+        None
+    };
+
     let transport = match_transport(transport, &transport_type);
 
     runtime!().spawn(async move {
@@ -60,6 +90,7 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe(
             on_state_changed_port: i64,
             on_transactions_found_port: i64,
             transport: Arc<dyn Transport>,
+            pending_messages: Option<Arc<PendingMessagesStorage>>,
             workchain: i8,
             public_key: String,
             contract: String,
@@ -75,19 +106,31 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe(
                 on_message_expired_port,
                 on_state_changed_port,
                 on_transactions_found_port,
+                pending_messages.clone(),
             ));
 
-            let ton_wallet = TonWallet::subscribe(
+            let mut ton_wallet = TonWallet::subscribe(
                 clock!(),
-                transport,
+                transport.clone(),
                 workchain,
                 public_key,
                 contract,
-                handler,
+                handler.clone(),
             )
             .await
             .handle_error()?;
 
+            // A failed restore keeps the stored messages, it can be retried explicitly
+            // without preventing the subscription from being created
+            if let Some(pending_messages) = pending_messages {
+                if let Ok(restored) =
+                    restore_pending_messages(&mut ton_wallet, &pending_messages, transport.as_ref())
+                        .await
+                {
+                    handler.on_pending_messages_restored(restored);
+                }
+            }
+
             let ptr = Box::into_raw(Box::new(RwLock::new(ton_wallet)));
 
             serde_json::to_value(ptr.to_ptr_address()).handle_error()
@@ -99,6 +142,7 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe(
             on_state_changed_port,
             on_transactions_found_port,
             transport,
+            pending_messages,
             workchain,
             public_key,
             contract,
@@ -121,11 +165,18 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe_by_address(
     on_transactions_found_port: c_longlong,
     transport: *mut c_void,
     transport_type: *mut c_char,
+    pending_messages: *mut c_void,
     address: *mut c_char,
 ) {
     let transport_type = transport_type.to_string_from_ptr();
     let address = address.to_string_from_ptr();
 
+    let pending_messages = if !pending_messages.is_null() {
+        Some((&*(pending_messages as *mut Arc<PendingMessagesStorage>)).clone())
+    } else {
+        None
+    };
+
     let transport = match_transport(transport, &transport_type);
 
     runtime!().spawn(async move {
@@ -135,6 +186,7 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe_by_address(
             on_state_changed_port: i64,
             on_transactions_found_port: i64,
             transport: Arc<dyn Transport>,
+            pending_messages: Option<Arc<PendingMessagesStorage>>,
             address: String,
         ) -> Result<serde_json::Value, String> {
             let address = parse_address(&address)?;
@@ -144,11 +196,28 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe_by_address(
                 on_message_expired_port,
                 on_state_changed_port,
                 on_transactions_found_port,
+                pending_messages.clone(),
             ));
 
-            let ton_wallet = TonWallet::subscribe_by_address(clock!(), transport, address, handler)
-                .await
-                .handle_error()?;
+            let mut ton_wallet = TonWallet::subscribe_by_address(
+                clock!(),
+                transport.clone(),
+                address,
+                handler.clone(),
+            )
+            .await
+            .handle_error()?;
+
+            // A failed restore keeps the stored messages, it can be retried explicitly
+            // without preventing the subscription from being created
+            if let Some(pending_messages) = pending_messages {
+                if let Ok(restored) =
+                    restore_pending_messages(&mut ton_wallet, &pending_messages, transport.as_ref())
+                        .await
+                {
+                    handler.on_pending_messages_restored(restored);
+                }
+            }
 
             let ptr = Box::into_raw(Box::new(RwLock::new(ton_wallet)));
 
@@ -161,6 +230,7 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe_by_address(
             on_state_changed_port,
             on_transactions_found_port,
             transport,
+            pending_messages,
             address,
         )
         .await
@@ -181,11 +251,18 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe_by_existing(
     on_transactions_found_port: c_longlong,
     transport: *mut c_void,
     transport_type: *mut c_char,
+    pending_messages: *mut c_void,
     existing_wallet: *mut c_char,
 ) {
     let transport_type = transport_type.to_string_from_ptr();
     let existing_wallet = existing_wallet.to_string_from_ptr();
 
+    let pending_messages = if !pending_messages.is_null() {
+        Some((&*(pending_messages as *mut Arc<PendingMessagesStorage>)).clone())
+    } else {
+        None
+    };
+
     let transport = match_transport(transport, &transport_type);
 
     runtime!().spawn(async move {
@@ -195,6 +272,7 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe_by_existing(
             on_state_changed_port: i64,
             on_transactions_found_port: i64,
             transport: Arc<dyn Transport>,
+            pending_messages: Option<Arc<PendingMessagesStorage>>,
             existing_wallet: String,
         ) -> Result<serde_json::Value, String> {
             let existing_wallet =
@@ -207,12 +285,28 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe_by_existing(
                 on_message_expired_port,
                 on_state_changed_port,
                 on_transactions_found_port,
+                pending_messages.clone(),
             ));
 
-            let ton_wallet =
-                TonWallet::subscribe_by_existing(clock!(), transport, existing_wallet, handler)
-                    .await
-                    .handle_error()?;
+            let mut ton_wallet = TonWallet::subscribe_by_existing(
+                clock!(),
+                transport.clone(),
+                existing_wallet,
+                handler.clone(),
+            )
+            .await
+            .handle_error()?;
+
+            // A failed restore keeps the stored messages, it can be retried explicitly
+            // without preventing the subscription from being created
+            if let Some(pending_messages) = pending_messages {
+                if let Ok(restored) =
+                    restore_pending_messages(&mut ton_wallet, &pending_messages, transport.as_ref())
+                        .await
+                {
+                    handler.on_pending_messages_restored(restored);
+                }
+            }
 
             let ptr = Box::into_raw(Box::new(RwLock::new(ton_wallet)));
 
@@ -225,6 +319,7 @@ pub unsafe extern "C" fn nt_ton_wallet_subscribe_by_existing(
             on_state_changed_port,
             on_transactions_found_port,
             transport,
+            pending_messages,
             existing_wallet,
         )
         .await
@@ -563,6 +658,8 @@ pub unsafe extern "C" fn nt_ton_wallet_prepare_transfer(
     amount: *mut c_char,
     bounce: c_uint,
     body: *mut c_char,
+    state_init: *mut c_char,
+    flags: *mut c_char,
     expiration: *mut c_char,
 ) {
     let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
@@ -572,6 +669,8 @@ pub unsafe extern "C" fn nt_ton_wallet_prepare_transfer(
     let destination = destination.to_string_from_ptr();
     let amount = amount.to_string_from_ptr();
     let body = body.to_optional_string_from_ptr();
+    let state_init = state_init.to_optional_string_from_ptr();
+    let flags = flags.to_optional_string_from_ptr();
     let expiration = expiration.to_string_from_ptr();
 
     runtime!().spawn(async move {
@@ -583,6 +682,8 @@ pub unsafe extern "C" fn nt_ton_wallet_prepare_transfer(
             amount: String,
             bounce: u32,
             body: Option<String>,
+            state_init: Option<String>,
+            flags: Option<String>,
             expiration: String,
         ) -> Result<serde_json::Value, String> {
             let contract_state = serde_json::from_str::<RawContractStateHelper>(&contract_state)
@@ -598,27 +699,28 @@ pub unsafe extern "C" fn nt_ton_wallet_prepare_transfer(
 
             let public_key = parse_public_key(&public_key)?;
 
-            let destination = parse_address(&destination)?;
-
-            let amount = amount.parse::<u64>().handle_error()?;
-
-            let bounce = bounce != 0;
-
             let body = body
-                .map(|e| create_boc_or_comment_payload(&e))
+                .map(|e| serde_json::from_str::<TransferBody>(&e))
                 .transpose()
                 .handle_error()?;
 
-            let expiration = serde_json::from_str::<Expiration>(&expiration).handle_error()?;
+            let flags = flags
+                .map(|e| serde_json::from_str::<MessageFlagsHelper>(&e))
+                .transpose()
+                .handle_error()?
+                .map(|MessageFlagsHelper(flags)| flags)
+                .unwrap_or_default();
 
-            let gift = Gift {
-                flags: MessageFlags::default().into(),
-                bounce,
+            let gift = make_gift(TransferRecipient {
                 destination,
                 amount,
+                bounce: bounce != 0,
                 body,
-                state_init: None,
-            };
+                state_init,
+                flags,
+            })?;
+
+            let expiration = serde_json::from_str::<Expiration>(&expiration).handle_error()?;
 
             let action = ton_wallet
                 .prepare_transfer(&current_state, &public_key, gift, expiration)
@@ -644,6 +746,122 @@ pub unsafe extern "C" fn nt_ton_wallet_prepare_transfer(
             amount,
             bounce,
             body,
+            state_init,
+            flags,
+            expiration,
+        )
+        .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_ton_wallet_prepare_batch_transfer(
+    result_port: c_longlong,
+    ton_wallet: *mut c_void,
+    contract_state: *mut c_char,
+    public_key: *mut c_char,
+    recipients: *mut c_char,
+    expiration: *mut c_char,
+) {
+    let ton_wallet = &*(ton_wallet as *mut RwLock<TonWallet>);
+
+    let contract_state = contract_state.to_string_from_ptr();
+    let public_key = public_key.to_string_from_ptr();
+    let recipients = recipients.to_string_from_ptr();
+    let expiration = expiration.to_string_from_ptr();
+
+    runtime!().spawn(async move {
+        fn internal_fn(
+            ton_wallet: &mut TonWallet,
+            contract_state: String,
+            public_key: String,
+            recipients: String,
+            expiration: String,
+        ) -> Result<serde_json::Value, String> {
+            let contract_state = serde_json::from_str::<RawContractStateHelper>(&contract_state)
+                .map(|RawContractStateHelper(raw_contract_state)| raw_contract_state)
+                .handle_error()?;
+
+            let current_state = match contract_state {
+                nekoton::transport::models::RawContractState::NotExists => {
+                    return Err("Not exists").handle_error()
+                },
+                nekoton::transport::models::RawContractState::Exists(contract) => contract.account,
+            };
+
+            let public_key = parse_public_key(&public_key)?;
+
+            let mut gifts = serde_json::from_str::<Vec<TransferRecipient>>(&recipients)
+                .handle_error()?
+                .into_iter()
+                .map(make_gift)
+                .collect::<Result<Vec<_>, String>>()?;
+
+            let expiration = serde_json::from_str::<Expiration>(&expiration).handle_error()?;
+
+            let max_messages = match ton_wallet.wallet_type() {
+                WalletType::Multisig(_) => MULTISIG_MAX_MESSAGES,
+                WalletType::WalletV3 => WALLET_V3_MAX_MESSAGES,
+                WalletType::HighloadWalletV2 => HIGHLOAD_WALLET_V2_MAX_MESSAGES,
+            };
+
+            if gifts.is_empty() {
+                return Err("No recipients").handle_error();
+            }
+
+            if gifts.len() > max_messages {
+                return Err(format!(
+                    "Too many recipients. Wallet supports up to {} messages",
+                    max_messages
+                ))
+                .handle_error();
+            }
+
+            let action = match ton_wallet.wallet_type() {
+                WalletType::WalletV3 => wallet_v3::prepare_transfer(
+                    clock!().as_ref(),
+                    &public_key,
+                    &current_state,
+                    gifts,
+                    expiration,
+                ),
+                WalletType::HighloadWalletV2 => highload_wallet_v2::prepare_transfer(
+                    clock!().as_ref(),
+                    &public_key,
+                    &current_state,
+                    gifts,
+                    expiration,
+                ),
+                WalletType::Multisig(_) => ton_wallet.prepare_transfer(
+                    &current_state,
+                    &public_key,
+                    gifts.remove(0),
+                    expiration,
+                ),
+            }
+            .handle_error()?;
+
+            let unsigned_message = match action {
+                TransferAction::DeployFirst => return Err("Deploy first").handle_error(),
+                TransferAction::Sign(unsigned_message) => unsigned_message,
+            };
+
+            let ptr = Box::into_raw(Box::new(RwLock::new(unsigned_message)));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        let mut ton_wallet = ton_wallet.write().await;
+
+        let result = internal_fn(
+            &mut ton_wallet,
+            contract_state,
+            public_key,
+            recipients,
             expiration,
         )
         .match_result();
@@ -943,6 +1161,187 @@ pub unsafe extern "C" fn nt_find_existing_wallets(
     });
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn nt_discover_wallets(
+    result_port: c_longlong,
+    on_wallet_found_port: c_longlong,
+    transport: *mut c_void,
+    transport_type: *mut c_char,
+    keystore: *mut c_void,
+    source: *mut c_char,
+    params: *mut c_char,
+) {
+    let transport_type = transport_type.to_string_from_ptr();
+    let keystore = (!keystore.is_null()).then(|| &**(keystore as *mut KeyStoreImpl));
+    let source = source.to_secret_string_from_ptr();
+    let params = params.to_string_from_ptr();
+
+    let transport = match_transport(transport, &transport_type);
+
+    runtime!().spawn(async move {
+        async fn internal_fn(
+            on_wallet_found_port: i64,
+            transport: Arc<dyn Transport>,
+            keystore: Option<&KeyStore>,
+            source: SecUtf8,
+            params: String,
+        ) -> Result<serde_json::Value, String> {
+            let on_wallet_found_port = Isolate::new(on_wallet_found_port);
+
+            let source =
+                serde_json::from_str::<WalletDiscoverySource>(source.unsecure()).handle_error()?;
+
+            let params = serde_json::from_str::<WalletDiscoveryParams>(&params).handle_error()?;
+
+            if params.gap_limit == 0 || params.gap_limit > MAX_DISCOVERY_GAP_LIMIT {
+                return Err("Gap limit is out of range").handle_error();
+            }
+
+            let wallet_types = params
+                .wallet_types
+                .into_iter()
+                .map(|WalletTypeHelper(wallet_type)| wallet_type)
+                .collect::<Vec<_>>();
+
+            let (phrase, language) = match source {
+                WalletDiscoverySource::Keystore(input) => {
+                    let keystore = keystore.ok_or("Keystore is required").handle_error()?;
+
+                    let output = keystore
+                        .export_key::<DerivedKeySigner>(input)
+                        .await
+                        .handle_error()?;
+
+                    (output.phrase, Language::English)
+                },
+                WalletDiscoverySource::Phrase { phrase, language } => {
+                    (phrase, parse_language(language)?)
+                },
+                WalletDiscoverySource::LegacyPhrase { phrase } => {
+                    let keypair = derive_from_phrase(phrase.unsecure(), MnemonicType::Legacy)
+                        .handle_error()?;
+
+                    let existing_wallets = find_active_wallets(
+                        transport.as_ref(),
+                        &keypair.public,
+                        &params.workchains,
+                        &wallet_types,
+                        &on_wallet_found_port,
+                    )
+                    .await?;
+
+                    return serde_json::to_value(&existing_wallets).handle_error();
+                },
+            };
+
+            let seed = phrase_to_seed(phrase.unsecure(), language)?;
+
+            let mut existing_wallets = Vec::new();
+            let mut gap = 0;
+
+            // Labs account ids are u16
+            for account_id in 0..=u16::MAX {
+                if gap >= params.gap_limit {
+                    break;
+                }
+
+                let public_key = derive_keypair(
+                    seed.as_bytes(),
+                    &labs_derivation_path(account_id),
+                    DerivationScheme::Bip32,
+                )?
+                .public;
+
+                let active_wallets = find_active_wallets(
+                    transport.as_ref(),
+                    &public_key,
+                    &params.workchains,
+                    &wallet_types,
+                    &on_wallet_found_port,
+                )
+                .await?;
+
+                match active_wallets.is_empty() {
+                    true => gap += 1,
+                    false => gap = 0,
+                }
+
+                existing_wallets.extend(active_wallets);
+            }
+
+            serde_json::to_value(&existing_wallets).handle_error()
+        }
+
+        let result = internal_fn(on_wallet_found_port, transport, keystore, source, params)
+            .await
+            .match_result();
+
+        Isolate::new(result_port)
+            .post_with_result(result.to_ptr_address())
+            .unwrap();
+    });
+}
+
+fn make_gift(recipient: TransferRecipient) -> Result<Gift, String> {
+    let destination = parse_address(&recipient.destination)?;
+
+    let amount = recipient.amount.parse::<u64>().handle_error()?;
+
+    let body = recipient
+        .body
+        .map(|e| match e {
+            TransferBody::Comment(comment) => create_comment_payload(&comment).handle_error(),
+            TransferBody::Boc(boc) => create_boc_payload(&boc).handle_error(),
+            TransferBody::EncryptedComment(payload) => create_encrypted_comment_payload(&payload),
+        })
+        .transpose()?;
+
+    let state_init = recipient
+        .state_init
+        .map(|e| StateInit::construct_from_base64(&e))
+        .transpose()
+        .handle_error()?;
+
+    Ok(Gift {
+        flags: recipient.flags.into(),
+        bounce: recipient.bounce,
+        destination,
+        amount,
+        body,
+        state_init,
+    })
+}
+
+async fn find_active_wallets(
+    transport: &dyn Transport,
+    public_key: &ed25519_dalek::PublicKey,
+    workchains: &[i8],
+    wallet_types: &[WalletType],
+    on_wallet_found_port: &Isolate,
+) -> Result<Vec<ExistingWalletInfoHelper>, String> {
+    let mut active_wallets = Vec::new();
+
+    for workchain_id in workchains {
+        let existing_wallets =
+            find_existing_wallets(transport, public_key, *workchain_id, wallet_types)
+                .await
+                .handle_error()?
+                .into_iter()
+                .filter(|e| e.contract_state.is_deployed || e.contract_state.balance > 0)
+                .map(ExistingWalletInfoHelper);
+
+        for existing_wallet in existing_wallets {
+            let payload = serde_json::to_string(&existing_wallet).handle_error()?;
+
+            on_wallet_found_port.post(payload);
+
+            active_wallets.push(existing_wallet);
+        }
+    }
+
+    Ok(active_wallets)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn nt_get_existing_wallet_info(
     result_port: c_longlong,
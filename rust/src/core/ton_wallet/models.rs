@@ -1,12 +1,16 @@
-use nekoton::core::{
-    models::ContractState,
-    ton_wallet::{ExistingWalletInfo, MultisigType, WalletType},
+use nekoton::{
+    core::{
+        models::{ContractState, MessageFlags},
+        ton_wallet::{ExistingWalletInfo, MultisigType, WalletType},
+    },
+    crypto::DerivedKeyExportParams,
 };
 use nekoton_utils::{serde_address, serde_public_key};
+use secstr::SecUtf8;
 use serde::{Deserialize, Serialize};
 use ton_block::MsgAddressInt;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WalletTypeHelper(#[serde(with = "WalletTypeDef")] pub WalletType);
 
 #[derive(Serialize, Deserialize)]
@@ -38,3 +42,79 @@ pub struct ExistingWalletInfoDef {
     pub wallet_type: WalletType,
     pub contract_state: ContractState,
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum WalletDiscoverySource {
+    Keystore(DerivedKeyExportParams),
+    #[serde(rename_all = "camelCase")]
+    Phrase {
+        phrase: SecUtf8,
+        language: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    LegacyPhrase {
+        phrase: SecUtf8,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletDiscoveryParams {
+    pub wallet_types: Vec<WalletTypeHelper>,
+    pub workchains: Vec<i8>,
+    pub gap_limit: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferRecipient {
+    pub destination: String,
+    pub amount: String,
+    pub bounce: bool,
+    pub body: Option<TransferBody>,
+    pub state_init: Option<String>,
+    #[serde(with = "MessageFlagsDef", default)]
+    pub flags: MessageFlags,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum TransferBody {
+    Comment(String),
+    Boc(String),
+    // Library specific encrypted comment format, see crypto::encrypted_comment
+    EncryptedComment(String),
+}
+
+#[derive(Deserialize)]
+pub struct MessageFlagsHelper(#[serde(with = "MessageFlagsDef")] pub MessageFlags);
+
+#[derive(Deserialize)]
+#[serde(remote = "MessageFlags", rename_all = "camelCase")]
+pub struct MessageFlagsDef {
+    #[serde(default = "default_all_balance")]
+    pub all_balance: bool,
+    #[serde(default = "default_pay_fees_separately")]
+    pub pay_fees_separately: bool,
+    #[serde(default = "default_ignore_errors")]
+    pub ignore_errors: bool,
+    #[serde(default = "default_destroy_if_zero")]
+    pub destroy_if_zero: bool,
+}
+
+fn default_all_balance() -> bool {
+    MessageFlags::default().all_balance
+}
+
+fn default_pay_fees_separately() -> bool {
+    MessageFlags::default().pay_fees_separately
+}
+
+fn default_ignore_errors() -> bool {
+    MessageFlags::default().ignore_errors
+}
+
+fn default_destroy_if_zero() -> bool {
+    MessageFlags::default().destroy_if_zero
+}
@@ -1 +1,214 @@
+pub(crate) mod models;
+
+use std::os::raw::c_char;
+
+use bip39::{Language, Mnemonic, Seed};
+use hmac::{Hmac, Mac, NewMac};
+use secstr::SecUtf8;
+use sha2::Sha512;
+use tiny_hderive::bip32::ExtendedPrivKey;
+use zeroize::Zeroizing;
+
+use crate::{
+    crypto::{
+        derived_key::models::{
+            DerivationPathParams, DerivationPathRange, DerivationScheme, DerivedPublicKey,
+        },
+        mnemonic::{models::KeypairHelper, parse_language},
+    },
+    HandleError, MatchResult, MatchSecretResult, ToOptionalStringFromPtr, ToSecretStringFromPtr,
+    ToStringFromPtr,
+};
+
 pub(crate) const DERIVED_KEY_SIGNER_NAME: &str = "DerivedKeySigner";
+
+const SLIP10_ED25519_SEED: &[u8] = b"ed25519 seed";
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+const PATH_INDEX_PLACEHOLDER: &str = "{index}";
+const MAX_DERIVATION_RANGE_COUNT: u32 = 100;
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_derive_from_phrase_by_path(
+    phrase: *mut c_char,
+    language: *mut c_char,
+    params: *mut c_char,
+) -> *mut c_char {
+    let phrase = phrase.to_secret_string_from_ptr();
+    let language = language.to_optional_string_from_ptr();
+    let params = params.to_string_from_ptr();
+
+    fn internal_fn(
+        phrase: SecUtf8,
+        language: Option<String>,
+        params: String,
+    ) -> Result<KeypairHelper, String> {
+        let language = parse_language(language)?;
+
+        let params = serde_json::from_str::<DerivationPathParams>(&params).handle_error()?;
+
+        let seed = phrase_to_seed(phrase.unsecure(), language)?;
+
+        let keypair = derive_keypair(seed.as_bytes(), &params.path, params.scheme)?;
+
+        Ok(KeypairHelper(keypair))
+    }
+
+    internal_fn(phrase, language, params).match_secret_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_derive_public_keys_by_path(
+    phrase: *mut c_char,
+    language: *mut c_char,
+    range: *mut c_char,
+) -> *mut c_char {
+    let phrase = phrase.to_secret_string_from_ptr();
+    let language = language.to_optional_string_from_ptr();
+    let range = range.to_string_from_ptr();
+
+    fn internal_fn(
+        phrase: SecUtf8,
+        language: Option<String>,
+        range: String,
+    ) -> Result<serde_json::Value, String> {
+        let language = parse_language(language)?;
+
+        let range = serde_json::from_str::<DerivationPathRange>(&range).handle_error()?;
+
+        let public_keys = derive_public_keys_by_path(phrase.unsecure(), language, &range)?;
+
+        serde_json::to_value(&public_keys).handle_error()
+    }
+
+    internal_fn(phrase, language, range).match_result()
+}
+
+pub(crate) fn derive_public_keys_by_path(
+    phrase: &str,
+    language: Language,
+    range: &DerivationPathRange,
+) -> Result<Vec<DerivedPublicKey>, String> {
+    if !range.path_template.contains(PATH_INDEX_PLACEHOLDER) {
+        return Err("Path template must contain an index placeholder").handle_error();
+    }
+
+    if range.count > MAX_DERIVATION_RANGE_COUNT {
+        return Err("Too many keys requested").handle_error();
+    }
+
+    let seed = phrase_to_seed(phrase, language)?;
+
+    (range.from..range.from.saturating_add(range.count))
+        .map(|index| {
+            let path = range
+                .path_template
+                .replace(PATH_INDEX_PLACEHOLDER, &index.to_string());
+
+            let public_key = derive_keypair(seed.as_bytes(), &path, range.scheme)?.public;
+
+            Ok(DerivedPublicKey { path, public_key })
+        })
+        .collect()
+}
+
+pub(crate) fn labs_derivation_path(account_id: u16) -> String {
+    format!("m/44'/396'/0'/0/{}", account_id)
+}
+
+pub(crate) fn phrase_to_seed(phrase: &str, language: Language) -> Result<Seed, String> {
+    let mnemonic = Mnemonic::from_phrase(phrase, language).handle_error()?;
+
+    Ok(Seed::new(&mnemonic, ""))
+}
+
+pub(crate) fn derive_keypair(
+    seed: &[u8],
+    path: &str,
+    scheme: DerivationScheme,
+) -> Result<ed25519_dalek::Keypair, String> {
+    let indices = parse_path(path)?;
+
+    let secret = match scheme {
+        DerivationScheme::Bip32 => Zeroizing::new(
+            ExtendedPrivKey::derive(seed, format_path(&indices).as_str())
+                .map_err(|e| format!("{:?}", e))?
+                .secret(),
+        ),
+        DerivationScheme::Slip10 => derive_slip10(seed, &indices)?,
+    };
+
+    let secret = ed25519_dalek::SecretKey::from_bytes(&secret[..]).handle_error()?;
+    let public = ed25519_dalek::PublicKey::from(&secret);
+
+    Ok(ed25519_dalek::Keypair { secret, public })
+}
+
+fn derive_slip10(seed: &[u8], indices: &[u32]) -> Result<Zeroizing<[u8; 32]>, String> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(SLIP10_ED25519_SEED).handle_error()?;
+    mac.update(seed);
+
+    let mut output = Zeroizing::new(mac.finalize().into_bytes().to_vec());
+
+    for index in indices {
+        if *index < HARDENED_OFFSET {
+            return Err("SLIP-10 supports only hardened derivation for ed25519").handle_error();
+        }
+
+        let mut mac = Hmac::<Sha512>::new_from_slice(&output[32..]).handle_error()?;
+        mac.update(&[0]);
+        mac.update(&output[..32]);
+        mac.update(&index.to_be_bytes());
+
+        output = Zeroizing::new(mac.finalize().into_bytes().to_vec());
+    }
+
+    let mut secret = Zeroizing::new([0; 32]);
+    secret.copy_from_slice(&output[..32]);
+
+    Ok(secret)
+}
+
+// Normalizes hardened suffixes to the form tiny-hderive accepts
+fn format_path(indices: &[u32]) -> String {
+    let mut path = String::from("m");
+
+    for index in indices {
+        match *index >= HARDENED_OFFSET {
+            true => path.push_str(&format!("/{}'", index - HARDENED_OFFSET)),
+            false => path.push_str(&format!("/{}", index)),
+        }
+    }
+
+    path
+}
+
+fn parse_path(path: &str) -> Result<Vec<u32>, String> {
+    let mut segments = path.split('/');
+
+    if segments.next() != Some("m") {
+        return Err("Derivation path must start with m").handle_error();
+    }
+
+    segments
+        .map(|segment| {
+            let (index, is_hardened) = match segment
+                .strip_suffix('\'')
+                .or_else(|| segment.strip_suffix('h'))
+            {
+                Some(index) => (index, true),
+                None => (segment, false),
+            };
+
+            let index = index.parse::<u32>().handle_error()?;
+
+            if index >= HARDENED_OFFSET {
+                return Err("Derivation path index is out of range").handle_error();
+            }
+
+            match is_hardened {
+                true => Ok(index | HARDENED_OFFSET),
+                false => Ok(index),
+            }
+        })
+        .collect()
+}
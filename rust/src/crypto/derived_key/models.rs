@@ -0,0 +1,33 @@
+use nekoton_utils::serde_public_key;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DerivationScheme {
+    Bip32,
+    Slip10,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DerivationPathParams {
+    pub path: String,
+    pub scheme: DerivationScheme,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DerivationPathRange {
+    pub path_template: String,
+    pub scheme: DerivationScheme,
+    pub from: u32,
+    pub count: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DerivedPublicKey {
+    pub path: String,
+    #[serde(with = "serde_public_key")]
+    pub public_key: ed25519_dalek::PublicKey,
+}
@@ -0,0 +1,157 @@
+use std::os::raw::c_char;
+
+use nekoton::{
+    core::models::Transaction,
+    crypto::{EncryptedData, EncryptionAlgorithm},
+};
+use nekoton_abi::create_boc_or_comment_payload;
+use ton_abi::{Param, ParamType, Token, TokenValue, Uint};
+use ton_types::SliceData;
+
+use crate::{HandleError, MatchResult, ToStringFromPtr};
+
+// Payload is `source public key | recipient public key | nonce | ciphertext`
+// stored as bytes, which doesn't match the standard encrypted comment layout,
+// so it uses its own opcode. The format is specific to this library, other
+// wallets show such comments as unknown payloads
+const ENCRYPTED_COMMENT_PREFIX: u32 = 0x4e4b4543;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_parse_encrypted_comment(payload: *mut c_char) -> *mut c_char {
+    let payload = payload.to_string_from_ptr();
+
+    fn internal_fn(payload: String) -> Result<serde_json::Value, String> {
+        let encrypted_data = unpack_encrypted_comment(&payload).ok();
+
+        serde_json::to_value(&encrypted_data).handle_error()
+    }
+
+    internal_fn(payload).match_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_decode_transaction_encrypted_comments(
+    transaction: *mut c_char,
+) -> *mut c_char {
+    let transaction = transaction.to_string_from_ptr();
+
+    fn internal_fn(transaction: String) -> Result<serde_json::Value, String> {
+        let transaction = serde_json::from_str::<Transaction>(&transaction).handle_error()?;
+
+        let encrypted_data = transaction_encrypted_comments(&transaction)?;
+
+        serde_json::to_value(&encrypted_data).handle_error()
+    }
+
+    internal_fn(transaction).match_result()
+}
+
+pub(crate) fn pack_encrypted_comment(encrypted_data: &EncryptedData) -> Result<String, String> {
+    let mut data =
+        Vec::with_capacity(PUBLIC_KEY_LENGTH * 2 + NONCE_LENGTH + encrypted_data.data.len());
+    data.extend_from_slice(encrypted_data.source_public_key.as_bytes());
+    data.extend_from_slice(encrypted_data.recipient_public_key.as_bytes());
+    data.extend_from_slice(&encrypted_data.nonce);
+    data.extend_from_slice(&encrypted_data.data);
+
+    let tokens = [
+        Token::new(
+            "prefix",
+            TokenValue::Uint(Uint::new(ENCRYPTED_COMMENT_PREFIX as u128, 32)),
+        ),
+        Token::new("data", TokenValue::Bytes(data)),
+    ];
+
+    let cell = nekoton_abi::pack_into_cell(&tokens).handle_error()?;
+    let bytes = ton_types::serialize_toc(&cell).handle_error()?;
+
+    Ok(base64::encode(&bytes))
+}
+
+pub(crate) fn unpack_encrypted_comment(payload: &str) -> Result<EncryptedData, String> {
+    decode_encrypted_comment(parse_payload(payload)?)
+}
+
+// Incoming comment goes first, outgoing ones follow in the order of messages
+pub(crate) fn transaction_encrypted_comments(
+    transaction: &Transaction,
+) -> Result<Vec<EncryptedData>, String> {
+    std::iter::once(&transaction.in_msg)
+        .chain(transaction.out_msgs.iter())
+        .filter_map(|message| match &message.body {
+            Some(body) if has_encrypted_comment_prefix(&body.data) => Some(body.data.clone()),
+            _ => None,
+        })
+        .map(decode_encrypted_comment)
+        .collect()
+}
+
+pub(crate) fn create_encrypted_comment_payload(payload: &str) -> Result<SliceData, String> {
+    let body = parse_payload(payload)?;
+
+    decode_encrypted_comment(body.clone())?;
+
+    Ok(body)
+}
+
+pub(crate) fn create_token_transfer_payload(payload: &str) -> Result<ton_types::Cell, String> {
+    // Malformed encrypted comments are rejected instead of being sent as is
+    match parse_payload(payload) {
+        Ok(body) if has_encrypted_comment_prefix(&body) => {
+            decode_encrypted_comment(body.clone())?;
+            Ok(body.into_cell())
+        },
+        _ => Ok(create_boc_or_comment_payload(payload)
+            .handle_error()?
+            .into_cell()),
+    }
+}
+
+fn parse_payload(payload: &str) -> Result<SliceData, String> {
+    let bytes = base64::decode(payload).handle_error()?;
+    let cell = ton_types::deserialize_tree_of_cells(&mut bytes.as_slice()).handle_error()?;
+
+    Ok(cell.into())
+}
+
+fn has_encrypted_comment_prefix(body: &SliceData) -> bool {
+    matches!(body.clone().get_next_u32(), Ok(prefix) if prefix == ENCRYPTED_COMMENT_PREFIX)
+}
+
+fn decode_encrypted_comment(body: SliceData) -> Result<EncryptedData, String> {
+    let params = [
+        Param::new("prefix", ParamType::Uint(32)),
+        Param::new("data", ParamType::Bytes),
+    ];
+
+    let tokens = nekoton_abi::unpack_from_cell(&params, body, false).handle_error()?;
+
+    match tokens.as_slice() {
+        [Token {
+            value: TokenValue::Uint(prefix),
+            ..
+        }, Token {
+            value: TokenValue::Bytes(data),
+            ..
+        }] if prefix.number == ENCRYPTED_COMMENT_PREFIX.into()
+            && data.len() > PUBLIC_KEY_LENGTH * 2 + NONCE_LENGTH =>
+        {
+            let (source_public_key, data) = data.split_at(PUBLIC_KEY_LENGTH);
+            let (recipient_public_key, data) = data.split_at(PUBLIC_KEY_LENGTH);
+            let (nonce, data) = data.split_at(NONCE_LENGTH);
+
+            Ok(EncryptedData {
+                algorithm: EncryptionAlgorithm::ChaCha20Poly1305,
+                source_public_key: ed25519_dalek::PublicKey::from_bytes(source_public_key)
+                    .handle_error()?,
+                recipient_public_key: ed25519_dalek::PublicKey::from_bytes(recipient_public_key)
+                    .handle_error()?,
+                data: data.to_vec(),
+                nonce: nonce.to_vec(),
+            })
+        },
+        _ => Err("Invalid encrypted comment").handle_error(),
+    }
+}
@@ -3,60 +3,249 @@ pub(crate) mod models;
 use std::os::raw::c_char;
 
 use anyhow::Result;
-use nekoton::crypto::{derive_from_phrase, dict, generate_key};
+use bip39::{Language, Mnemonic};
+use nekoton::crypto::{derive_from_phrase, dict, generate_key, MnemonicType};
+use secstr::SecUtf8;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
 
 use crate::{
-    crypto::mnemonic::models::{GeneratedKeyHelper, KeypairHelper, MnemonicTypeHelper},
-    HandleError, MatchResult, ToStringFromPtr,
+    crypto::{
+        derived_key::{
+            derive_keypair, labs_derivation_path, models::DerivationScheme, phrase_to_seed,
+        },
+        mnemonic::models::{
+            GeneratedKeyHelper, KeypairHelper, MnemonicTypeHelper, PhraseValidation, UnknownWord,
+        },
+    },
+    HandleError, MatchResult, MatchSecretResult, ToOptionalStringFromPtr, ToSecretStringFromPtr,
+    ToStringFromPtr,
 };
 
+const LEGACY_WORD_COUNT: usize = 24;
+const MAX_SUGGESTIONS: usize = 5;
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+const LEGACY_LANGUAGE_ERROR: &str = "Legacy mnemonic supports only english";
+const KEYSTORE_LANGUAGE_ERROR: &str = "Keystore supports only english phrases";
+
 #[no_mangle]
-pub unsafe extern "C" fn nt_generate_key(mnemonic_type: *mut c_char) -> *mut c_char {
+pub unsafe extern "C" fn nt_generate_key(
+    mnemonic_type: *mut c_char,
+    language: *mut c_char,
+) -> *mut c_char {
     let mnemonic_type = mnemonic_type.to_string_from_ptr();
+    let language = language.to_optional_string_from_ptr();
 
-    fn internal_fn(mnemonic_type: String) -> Result<serde_json::Value, String> {
+    fn internal_fn(
+        mnemonic_type: String,
+        language: Option<String>,
+    ) -> Result<GeneratedKeyHelper, String> {
         let mnemonic_type = serde_json::from_str::<MnemonicTypeHelper>(&mnemonic_type)
             .map(|MnemonicTypeHelper(mnemonic_type)| mnemonic_type)
             .handle_error()?;
 
-        let generated_key = generate_key(mnemonic_type);
+        let language = parse_language(language)?;
+
+        // Keystore signers store only english phrases, so generating others would
+        // produce keys which can't be added
+        let generated_key = match (mnemonic_type, language) {
+            (_, Language::English) => generate_key(mnemonic_type),
+            (MnemonicType::Labs(_), _) => return Err(KEYSTORE_LANGUAGE_ERROR).handle_error(),
+            (MnemonicType::Legacy, _) => return Err(LEGACY_LANGUAGE_ERROR).handle_error(),
+        };
 
-        serde_json::to_value(&GeneratedKeyHelper(generated_key)).handle_error()
+        Ok(GeneratedKeyHelper(generated_key))
     }
 
-    internal_fn(mnemonic_type).match_result()
+    internal_fn(mnemonic_type, language).match_secret_result()
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn nt_get_hints(input: *mut c_char) -> *mut c_char {
-    let input = input.to_string_from_ptr();
+pub unsafe extern "C" fn nt_get_hints(input: *mut c_char, language: *mut c_char) -> *mut c_char {
+    let input = input.to_secret_string_from_ptr();
+    let language = language.to_optional_string_from_ptr();
 
-    fn internal_fn(input: String) -> Result<serde_json::Value, String> {
-        let hints = dict::get_hints(&input);
+    fn internal_fn(input: SecUtf8, language: Option<String>) -> Result<serde_json::Value, String> {
+        let hints = match parse_language(language)? {
+            Language::English => dict::get_hints(input.unsecure()),
+            language => {
+                let input = Zeroizing::new(input.unsecure().nfkd().collect::<String>());
+
+                language.wordlist().get_words_by_prefix(&input).to_vec()
+            },
+        };
 
         serde_json::to_value(&hints).handle_error()
     }
 
-    internal_fn(input).match_result()
+    internal_fn(input, language).match_result()
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_derive_from_phrase(
     phrase: *mut c_char,
     mnemonic_type: *mut c_char,
+    language: *mut c_char,
 ) -> *mut c_char {
-    let phrase = phrase.to_string_from_ptr();
+    let phrase = phrase.to_secret_string_from_ptr();
     let mnemonic_type = mnemonic_type.to_string_from_ptr();
+    let language = language.to_optional_string_from_ptr();
 
-    fn internal_fn(phrase: String, mnemonic_type: String) -> Result<serde_json::Value, String> {
+    fn internal_fn(
+        phrase: SecUtf8,
+        mnemonic_type: String,
+        language: Option<String>,
+    ) -> Result<KeypairHelper, String> {
         let mnemonic_type = serde_json::from_str::<MnemonicTypeHelper>(&mnemonic_type)
             .map(|MnemonicTypeHelper(mnemonic_type)| mnemonic_type)
             .handle_error()?;
 
-        let keypair = derive_from_phrase(&phrase, mnemonic_type).handle_error()?;
+        let language = parse_language(language)?;
+
+        let keypair = match (mnemonic_type, language) {
+            (_, Language::English) => {
+                derive_from_phrase(phrase.unsecure(), mnemonic_type).handle_error()?
+            },
+            (MnemonicType::Labs(account_id), language) => {
+                derive_labs_from_phrase(phrase.unsecure(), account_id, language)?
+            },
+            (MnemonicType::Legacy, _) => return Err(LEGACY_LANGUAGE_ERROR).handle_error(),
+        };
+
+        Ok(KeypairHelper(keypair))
+    }
+
+    internal_fn(phrase, mnemonic_type, language).match_secret_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_validate_phrase(
+    phrase: *mut c_char,
+    language: *mut c_char,
+) -> *mut c_char {
+    let phrase = phrase.to_secret_string_from_ptr();
+    let language = language.to_optional_string_from_ptr();
+
+    fn internal_fn(phrase: SecUtf8, language: Option<String>) -> Result<PhraseValidation, String> {
+        let language = parse_language(language)?;
+
+        let wordmap = language.wordmap();
+
+        let words = phrase
+            .unsecure()
+            .split_whitespace()
+            .map(normalize_word)
+            .collect::<Vec<_>>();
+
+        let unknown_words = words
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| wordmap.get_bits(word).is_err())
+            .map(|(position, word)| UnknownWord {
+                position,
+                suggestions: get_suggestions(word, language),
+            })
+            .collect::<Vec<_>>();
+
+        let phrase = Zeroizing::new(
+            words
+                .iter()
+                .map(|word| word.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+
+        let is_checksum_valid =
+            unknown_words.is_empty() && Mnemonic::validate(&phrase, language).is_ok();
+
+        let mut compatible_types = Vec::new();
+
+        if unknown_words.is_empty()
+            && words.len() == LEGACY_WORD_COUNT
+            && language == Language::English
+        {
+            compatible_types.push(MnemonicTypeHelper(MnemonicType::Legacy));
+        }
+
+        if is_checksum_valid {
+            compatible_types.push(MnemonicTypeHelper(MnemonicType::Labs(0)));
+        }
+
+        Ok(PhraseValidation {
+            word_count: words.len(),
+            unknown_words,
+            is_checksum_valid,
+            compatible_types,
+        })
+    }
+
+    internal_fn(phrase, language).match_secret_result()
+}
+
+pub(crate) fn parse_language(language: Option<String>) -> Result<Language, String> {
+    match language {
+        Some(language) => Language::from_language_code(&language)
+            .ok_or("Unsupported mnemonic language")
+            .handle_error(),
+        None => Ok(Language::English),
+    }
+}
+
+fn derive_labs_from_phrase(
+    phrase: &str,
+    account_id: u16,
+    language: Language,
+) -> Result<ed25519_dalek::Keypair, String> {
+    let seed = phrase_to_seed(phrase, language)?;
+
+    derive_keypair(
+        seed.as_bytes(),
+        &labs_derivation_path(account_id),
+        DerivationScheme::Bip32,
+    )
+}
+
+fn normalize_word(word: &str) -> Zeroizing<String> {
+    let word = Zeroizing::new(word.trim().to_lowercase());
+
+    Zeroizing::new(word.nfkd().collect::<String>())
+}
+
+fn get_suggestions(word: &str, language: Language) -> Vec<&'static str> {
+    let mut suggestions = language
+        .wordlist()
+        .get_words_by_prefix("")
+        .iter()
+        .map(|candidate| (levenshtein_distance(word, candidate), *candidate))
+        .filter(|(distance, candidate)| {
+            *distance <= MAX_SUGGESTION_DISTANCE || candidate.starts_with(word)
+        })
+        .collect::<Vec<_>>();
+
+    suggestions.sort_by_key(|(distance, _)| *distance);
+
+    suggestions
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
 
-        serde_json::to_value(&KeypairHelper(keypair)).handle_error()
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
     }
 
-    internal_fn(phrase, mnemonic_type).match_result()
+    row[b.len()]
 }
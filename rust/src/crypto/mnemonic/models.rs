@@ -39,3 +39,18 @@ pub struct KeypairDef {
     #[serde(with = "serde_secret_key")]
     pub secret: ed25519_dalek::SecretKey,
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhraseValidation {
+    pub word_count: usize,
+    pub unknown_words: Vec<UnknownWord>,
+    pub is_checksum_valid: bool,
+    pub compatible_types: Vec<MnemonicTypeHelper>,
+}
+
+#[derive(Serialize)]
+pub struct UnknownWord {
+    pub position: usize,
+    pub suggestions: Vec<&'static str>,
+}
@@ -1,21 +1,31 @@
 pub(crate) mod derived_key;
+pub(crate) mod encrypted_comment;
 pub(crate) mod encrypted_key;
 pub(crate) mod ledger_key;
-mod mnemonic;
+pub(crate) mod mnemonic;
 pub(crate) mod models;
+mod shamir;
 
 use std::os::raw::{c_char, c_longlong, c_void};
 
 use allo_isolate::Isolate;
 use ed25519_dalek::Verifier;
 use nekoton::crypto::UnsignedMessage;
+use sha2::Digest;
 use tokio::sync::RwLock;
 
 use crate::{
-    clock, parse_public_key, runtime, HandleError, MatchResult, PostWithResult, ToPtrAddress,
+    clock,
+    crypto::models::{
+        DataEncoding, InvalidSignatureReason, SignatureVerificationRequest,
+        SignatureVerificationResult, SignedDataMode, TypedMessage,
+    },
+    parse_public_key, runtime, HandleError, MatchResult, PostWithResult, ToPtrAddress,
     ToStringFromPtr, CLOCK, RUNTIME,
 };
 
+const TYPED_MESSAGE_PREFIX: &[u8] = b"\xffton-typed-message\x00";
+
 #[no_mangle]
 pub unsafe extern "C" fn nt_unsigned_message_refresh_timeout(
     result_port: c_longlong,
@@ -167,18 +177,7 @@ pub unsafe extern "C" fn nt_verify_signature(
             return Err("Invalid data hash. Expected 32 bytes").handle_error();
         }
 
-        let signature = match base64::decode(&signature) {
-            Ok(signature) => signature,
-            Err(e) => match hex::decode(&signature) {
-                Ok(signature) => signature,
-                Err(_) => return Err(e).handle_error(),
-            },
-        };
-
-        let signature = match ed25519_dalek::Signature::try_from(signature.as_slice()) {
-            Ok(signature) => signature,
-            Err(_) => return Err("Invalid signature. Expected 64 bytes").handle_error(),
-        };
+        let signature = parse_signature(&signature)?;
 
         let is_valid = public_key.verify(&data_hash, &signature).is_ok();
 
@@ -187,3 +186,219 @@ pub unsafe extern "C" fn nt_verify_signature(
 
     internal_fn(public_key, data_hash, signature).match_result()
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_verify_typed_message_signature(
+    public_key: *mut c_char,
+    message: *mut c_char,
+    signature: *mut c_char,
+) -> *mut c_char {
+    let public_key = public_key.to_string_from_ptr();
+    let message = message.to_string_from_ptr();
+    let signature = signature.to_string_from_ptr();
+
+    fn internal_fn(
+        public_key: String,
+        message: String,
+        signature: String,
+    ) -> Result<serde_json::Value, String> {
+        let public_key = parse_public_key(&public_key)?;
+
+        let message = serde_json::from_str::<TypedMessage>(&message).handle_error()?;
+
+        let signature = parse_signature(&signature)?;
+
+        let is_valid = verify_typed_message(&public_key, &message, &signature)?;
+
+        serde_json::to_value(is_valid).handle_error()
+    }
+
+    internal_fn(public_key, message, signature).match_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_verify_data_signature(request: *mut c_char) -> *mut c_char {
+    let request = request.to_string_from_ptr();
+
+    fn internal_fn(request: String) -> Result<serde_json::Value, String> {
+        let request =
+            serde_json::from_str::<SignatureVerificationRequest>(&request).handle_error()?;
+
+        let result = verify_data_signature(&request);
+
+        serde_json::to_value(&result).handle_error()
+    }
+
+    internal_fn(request).match_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_verify_data_signatures(requests: *mut c_char) -> *mut c_char {
+    let requests = requests.to_string_from_ptr();
+
+    fn internal_fn(requests: String) -> Result<serde_json::Value, String> {
+        let requests =
+            serde_json::from_str::<Vec<SignatureVerificationRequest>>(&requests).handle_error()?;
+
+        let results = requests
+            .iter()
+            .map(verify_data_signature)
+            .collect::<Vec<_>>();
+
+        serde_json::to_value(&results).handle_error()
+    }
+
+    internal_fn(requests).match_result()
+}
+
+fn verify_data_signature(request: &SignatureVerificationRequest) -> SignatureVerificationResult {
+    fn verify(request: &SignatureVerificationRequest) -> Result<(), InvalidSignatureReason> {
+        let public_key = parse_public_key(&request.public_key)
+            .map_err(|_| InvalidSignatureReason::InvalidPublicKey)?;
+
+        let data = decode_data(&request.data, request.data_encoding)
+            .map_err(|_| InvalidSignatureReason::InvalidDataEncoding)?;
+
+        let data = match request.data_mode {
+            SignedDataMode::Raw => data,
+            SignedDataMode::Sha256 => sha2::Sha256::digest(&data).to_vec(),
+        };
+
+        let signature = decode_data(&request.signature, request.signature_encoding)
+            .map_err(|_| InvalidSignatureReason::InvalidSignatureEncoding)?;
+
+        let signature = ed25519_dalek::Signature::try_from(signature.as_slice())
+            .map_err(|_| InvalidSignatureReason::InvalidSignatureLength)?;
+
+        public_key
+            .verify(&data, &signature)
+            .map_err(|_| InvalidSignatureReason::SignatureMismatch)
+    }
+
+    match verify(request) {
+        Ok(_) => SignatureVerificationResult {
+            is_valid: true,
+            reason: None,
+        },
+        Err(reason) => SignatureVerificationResult {
+            is_valid: false,
+            reason: Some(reason),
+        },
+    }
+}
+
+fn decode_data(data: &str, encoding: DataEncoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        DataEncoding::Hex => hex::decode(data).handle_error(),
+        DataEncoding::Base64 => base64::decode(data).handle_error(),
+        DataEncoding::Utf8 => Ok(data.as_bytes().to_vec()),
+    }
+}
+
+// Typed messages are signed over the prefixed preimage itself. Raw signing
+// rejects data with the same prefix, and hashed signing can't produce it, so
+// other signing paths can't forge them
+pub(crate) fn check_raw_signing_data(data: &[u8]) -> Result<(), String> {
+    if data.starts_with(TYPED_MESSAGE_PREFIX) {
+        return Err("Typed messages can only be signed as typed messages").handle_error();
+    }
+
+    Ok(())
+}
+
+fn verify_typed_message(
+    public_key: &ed25519_dalek::PublicKey,
+    message: &TypedMessage,
+    signature: &ed25519_dalek::Signature,
+) -> Result<bool, String> {
+    let preimage = typed_message_preimage(message)?;
+
+    Ok(public_key.verify(&preimage, signature).is_ok())
+}
+
+pub(crate) fn typed_message_preimage(message: &TypedMessage) -> Result<Vec<u8>, String> {
+    let data = base64::decode(&message.data).handle_error()?;
+
+    let mut preimage = TYPED_MESSAGE_PREFIX.to_vec();
+
+    for field in [
+        message.domain.origin.as_bytes(),
+        message.domain.purpose.as_bytes(),
+    ] {
+        preimage.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        preimage.extend_from_slice(field);
+    }
+
+    preimage.extend_from_slice(&message.domain.network_id.to_be_bytes());
+    preimage.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    preimage.extend_from_slice(&data);
+
+    Ok(preimage)
+}
+
+fn parse_signature(signature: &str) -> Result<ed25519_dalek::Signature, String> {
+    let signature = match base64::decode(signature) {
+        Ok(signature) => signature,
+        Err(e) => match hex::decode(signature) {
+            Ok(signature) => signature,
+            Err(_) => return Err(e).handle_error(),
+        },
+    };
+
+    match ed25519_dalek::Signature::try_from(signature.as_slice()) {
+        Ok(signature) => Ok(signature),
+        Err(_) => Err("Invalid signature. Expected 64 bytes").handle_error(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::Signer;
+
+    use super::*;
+    use crate::crypto::models::MessageDomain;
+
+    fn keypair() -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn typed_message() -> TypedMessage {
+        TypedMessage {
+            domain: MessageDomain {
+                origin: "https://example.com".to_owned(),
+                purpose: "login".to_owned(),
+                network_id: 42,
+            },
+            data: base64::encode("hello"),
+        }
+    }
+
+    #[test]
+    fn typed_message_signature_differs_from_data_signature() {
+        let keypair = keypair();
+        let message = typed_message();
+
+        let preimage = typed_message_preimage(&message).unwrap();
+        let typed_signature = keypair.sign(&preimage);
+
+        // Signing the preimage as untyped data signs its hash instead
+        let data_hash: [u8; 32] = sha2::Sha256::digest(&preimage).into();
+        let data_signature = keypair.sign(&data_hash);
+
+        assert_ne!(typed_signature, data_signature);
+
+        assert!(verify_typed_message(&keypair.public, &message, &typed_signature).unwrap());
+        assert!(!verify_typed_message(&keypair.public, &message, &data_signature).unwrap());
+    }
+
+    #[test]
+    fn raw_signing_rejects_typed_message_preimage() {
+        let preimage = typed_message_preimage(&typed_message()).unwrap();
+
+        assert!(check_raw_signing_data(&preimage).is_err());
+        assert!(check_raw_signing_data(b"hello").is_ok());
+    }
+}
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,8 +17,75 @@ pub struct SignedDataRaw {
     pub signature_parts: SignatureParts,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedTypedMessage {
+    pub preimage: String,
+    pub signature: String,
+    pub signature_hex: String,
+    pub signature_parts: SignatureParts,
+}
+
 #[derive(Serialize)]
 pub struct SignatureParts {
     pub high: String,
     pub low: String,
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedMessage {
+    pub domain: MessageDomain,
+    pub data: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageDomain {
+    pub origin: String,
+    pub purpose: String,
+    pub network_id: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureVerificationRequest {
+    pub public_key: String,
+    pub data: String,
+    pub data_encoding: DataEncoding,
+    pub data_mode: SignedDataMode,
+    pub signature: String,
+    pub signature_encoding: DataEncoding,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataEncoding {
+    Hex,
+    Base64,
+    Utf8,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignedDataMode {
+    Raw,
+    Sha256,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureVerificationResult {
+    pub is_valid: bool,
+    pub reason: Option<InvalidSignatureReason>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InvalidSignatureReason {
+    InvalidPublicKey,
+    InvalidDataEncoding,
+    InvalidSignatureEncoding,
+    InvalidSignatureLength,
+    SignatureMismatch,
+}
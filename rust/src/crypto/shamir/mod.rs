@@ -0,0 +1,579 @@
+mod models;
+
+use std::{
+    collections::HashSet,
+    os::raw::{c_char, c_uchar},
+};
+
+use bip39::Language;
+use rand::{rngs::OsRng, RngCore};
+use secstr::SecUtf8;
+use sha2::Digest;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+use crate::{
+    crypto::{mnemonic::parse_language, shamir::models::ShamirShareInfo},
+    HandleError, MatchResult, MatchSecretResult, ToOptionalStringFromPtr, ToSecretStringFromPtr,
+};
+
+// The shared secret is `word count (1) | word indices packed by 11 bits |
+// digest (4)`, where the digest is the first 4 bytes of SHA-256 over the
+// preceding bytes.
+//
+// A share is `version (1) | identifier (2) | threshold (1) | index (1) | length
+// (1) | value (length) | checksum (4)`, where the checksum is the first 4 bytes
+// of SHA-256 over the preceding bytes. Shares are written down as english BIP39
+// words.
+const SHARE_VERSION: u8 = 0;
+const SHARE_HEADER_LENGTH: usize = 6;
+const CHECKSUM_LENGTH: usize = 4;
+const DIGEST_LENGTH: usize = 4;
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_shamir_split(
+    phrase: *mut c_char,
+    language: *mut c_char,
+    threshold: c_uchar,
+    share_count: c_uchar,
+) -> *mut c_char {
+    let phrase = phrase.to_secret_string_from_ptr();
+    let language = language.to_optional_string_from_ptr();
+
+    fn internal_fn(
+        phrase: SecUtf8,
+        language: Option<String>,
+        threshold: u8,
+        share_count: u8,
+    ) -> Result<Vec<SecUtf8>, String> {
+        let language = parse_language(language)?;
+
+        split_phrase(phrase.unsecure(), language, threshold, share_count)
+    }
+
+    internal_fn(phrase, language, threshold, share_count).match_secret_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_shamir_combine(
+    shares: *mut c_char,
+    language: *mut c_char,
+) -> *mut c_char {
+    let shares = shares.to_secret_string_from_ptr();
+    let language = language.to_optional_string_from_ptr();
+
+    fn internal_fn(shares: SecUtf8, language: Option<String>) -> Result<SecUtf8, String> {
+        let language = parse_language(language)?;
+
+        let shares = serde_json::from_str::<Vec<SecUtf8>>(shares.unsecure()).handle_error()?;
+
+        combine_phrase(&shares, language)
+    }
+
+    internal_fn(shares, language).match_secret_result()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_shamir_validate_share(share: *mut c_char) -> *mut c_char {
+    let share = share.to_secret_string_from_ptr();
+
+    fn internal_fn(share: SecUtf8) -> Result<serde_json::Value, String> {
+        let share = decode_share(share.unsecure())?;
+
+        let info = ShamirShareInfo {
+            identifier: share.identifier,
+            threshold: share.threshold,
+            index: share.index,
+            is_checksum_valid: share.is_checksum_valid,
+        };
+
+        serde_json::to_value(&info).handle_error()
+    }
+
+    internal_fn(share).match_result()
+}
+
+struct Share {
+    identifier: u16,
+    threshold: u8,
+    index: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+struct DecodedShare {
+    identifier: u16,
+    threshold: u8,
+    index: u8,
+    value: Zeroizing<Vec<u8>>,
+    is_checksum_valid: bool,
+}
+
+fn split_phrase(
+    phrase: &str,
+    language: Language,
+    threshold: u8,
+    share_count: u8,
+) -> Result<Vec<SecUtf8>, String> {
+    if threshold == 0 || threshold > share_count {
+        return Err("Invalid threshold").handle_error();
+    }
+
+    let secret = phrase_to_secret(phrase, language)?;
+
+    let identifier = OsRng.next_u32() as u16;
+
+    split_secret(&secret, threshold, share_count)
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let share = Share {
+                identifier,
+                threshold,
+                index: i as u8 + 1,
+                value,
+            };
+
+            encode_share(&share)
+        })
+        .collect()
+}
+
+fn combine_phrase(shares: &[SecUtf8], language: Language) -> Result<SecUtf8, String> {
+    let shares = shares
+        .iter()
+        .map(|share| decode_share(share.unsecure()))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let first = shares.first().ok_or("No shares provided").handle_error()?;
+
+    if shares.iter().any(|share| !share.is_checksum_valid) {
+        return Err("Invalid share checksum").handle_error();
+    }
+
+    if shares.iter().any(|share| {
+        share.identifier != first.identifier
+            || share.threshold != first.threshold
+            || share.value.len() != first.value.len()
+    }) {
+        return Err("Shares belong to different backups").handle_error();
+    }
+
+    let mut indices = HashSet::new();
+    if !shares.iter().all(|share| indices.insert(share.index)) {
+        return Err("Duplicate shares provided").handle_error();
+    }
+
+    if shares.len() < first.threshold as usize {
+        return Err("Not enough shares").handle_error();
+    }
+
+    let secret = combine_shares(&shares[..first.threshold as usize]);
+
+    secret_to_phrase(&secret, language)
+}
+
+fn phrase_to_secret(phrase: &str, language: Language) -> Result<Zeroizing<Vec<u8>>, String> {
+    let wordmap = language.wordmap();
+
+    let indices = Zeroizing::new(
+        phrase
+            .split_whitespace()
+            .map(|word| {
+                let word = Zeroizing::new(word.nfkd().collect::<String>());
+
+                wordmap.get_bits(&word).map(u16::from).handle_error()
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+    );
+
+    if indices.is_empty() || indices.len() > u8::MAX as usize {
+        return Err("Invalid word count").handle_error();
+    }
+
+    let mut secret = Zeroizing::new(vec![indices.len() as u8]);
+    secret.extend_from_slice(&pack_11_bits(&indices));
+
+    let digest = sha2::Sha256::digest(&secret);
+    secret.extend_from_slice(&digest[..DIGEST_LENGTH]);
+
+    Ok(secret)
+}
+
+fn secret_to_phrase(secret: &[u8], language: Language) -> Result<SecUtf8, String> {
+    if secret.len() <= DIGEST_LENGTH + 1 {
+        return Err("Invalid secret").handle_error();
+    }
+
+    let (data, digest) = secret.split_at(secret.len() - DIGEST_LENGTH);
+
+    if sha2::Sha256::digest(data)[..DIGEST_LENGTH] != *digest {
+        return Err("Invalid secret digest").handle_error();
+    }
+
+    let word_count = data[0] as usize;
+
+    let indices = unpack_11_bits(&data[1..]);
+
+    if indices.len() < word_count {
+        return Err("Invalid secret").handle_error();
+    }
+
+    let wordlist = language.wordlist();
+
+    let phrase = indices[..word_count]
+        .iter()
+        .map(|index| wordlist.get_word((*index).into()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(SecUtf8::from(phrase))
+}
+
+fn encode_share(share: &Share) -> Result<SecUtf8, String> {
+    if share.value.len() > u8::MAX as usize {
+        return Err("Secret is too long").handle_error();
+    }
+
+    let mut bytes = Zeroizing::new(Vec::with_capacity(
+        SHARE_HEADER_LENGTH + share.value.len() + CHECKSUM_LENGTH,
+    ));
+    bytes.push(SHARE_VERSION);
+    bytes.extend_from_slice(&share.identifier.to_be_bytes());
+    bytes.push(share.threshold);
+    bytes.push(share.index);
+    bytes.push(share.value.len() as u8);
+    bytes.extend_from_slice(&share.value);
+
+    let checksum = sha2::Sha256::digest(&bytes);
+    bytes.extend_from_slice(&checksum[..CHECKSUM_LENGTH]);
+
+    let wordlist = Language::English.wordlist();
+
+    let words = unpack_11_bits(&bytes)
+        .iter()
+        .map(|index| wordlist.get_word((*index).into()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(SecUtf8::from(words))
+}
+
+fn decode_share(share: &str) -> Result<DecodedShare, String> {
+    let wordmap = Language::English.wordmap();
+
+    let indices = Zeroizing::new(
+        share
+            .split_whitespace()
+            .map(|word| wordmap.get_bits(word).map(u16::from).handle_error())
+            .collect::<Result<Vec<_>, String>>()?,
+    );
+
+    let bytes = pack_11_bits(&indices);
+
+    if bytes.len() < SHARE_HEADER_LENGTH + CHECKSUM_LENGTH || bytes[0] != SHARE_VERSION {
+        return Err("Invalid share").handle_error();
+    }
+
+    let value_length = bytes[5] as usize;
+    let data_length = SHARE_HEADER_LENGTH + value_length;
+
+    if indices.len() != ((data_length + CHECKSUM_LENGTH) * 8 + 10) / 11 {
+        return Err("Invalid share length").handle_error();
+    }
+
+    let (data, checksum) = bytes.split_at(data_length);
+
+    let is_checksum_valid =
+        sha2::Sha256::digest(data)[..CHECKSUM_LENGTH] == checksum[..CHECKSUM_LENGTH];
+
+    // Share indices are evaluation points, zero is the secret itself
+    if data[3] == 0 || data[4] == 0 {
+        return Err("Invalid share").handle_error();
+    }
+
+    Ok(DecodedShare {
+        identifier: u16::from_be_bytes([data[1], data[2]]),
+        threshold: data[3],
+        index: data[4],
+        value: Zeroizing::new(data[SHARE_HEADER_LENGTH..].to_vec()),
+        is_checksum_valid,
+    })
+}
+
+fn split_secret(secret: &[u8], threshold: u8, share_count: u8) -> Vec<Zeroizing<Vec<u8>>> {
+    let mut shares = (0..share_count)
+        .map(|_| Zeroizing::new(Vec::with_capacity(secret.len())))
+        .collect::<Vec<_>>();
+
+    let mut coefficients = Zeroizing::new(vec![0u8; threshold as usize]);
+
+    for byte in secret {
+        coefficients[0] = *byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+
+        for (i, share) in shares.iter_mut().enumerate() {
+            let x = i as u8 + 1;
+
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(0, |acc, coefficient| gf_mul(acc, x) ^ coefficient);
+
+            share.push(y);
+        }
+    }
+
+    shares
+}
+
+fn combine_shares(shares: &[DecodedShare]) -> Zeroizing<Vec<u8>> {
+    let length = shares[0].value.len();
+
+    let mut secret = Zeroizing::new(Vec::with_capacity(length));
+
+    for position in 0..length {
+        let mut value = 0;
+
+        for (i, share) in shares.iter().enumerate() {
+            let basis = shares
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(1, |acc, (_, other)| {
+                    gf_mul(acc, gf_div(other.index, other.index ^ share.index))
+                });
+
+            value ^= gf_mul(share.value[position], basis);
+        }
+
+        secret.push(value);
+    }
+
+    secret
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut result = 0;
+
+    while b != 0 {
+        if b & 1 != 0 {
+            result ^= a;
+        }
+
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+
+        b >>= 1;
+    }
+
+    result
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    let mut inverse = 1;
+    let mut base = b;
+    let mut exponent = 254u8;
+
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            inverse = gf_mul(inverse, base);
+        }
+
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+
+    gf_mul(a, inverse)
+}
+
+fn pack_11_bits(values: &[u16]) -> Zeroizing<Vec<u8>> {
+    let mut bytes = Zeroizing::new(Vec::with_capacity((values.len() * 11 + 7) / 8));
+
+    let mut accumulator = 0u32;
+    let mut accumulator_bits = 0;
+
+    for value in values {
+        accumulator = (accumulator << 11) | (*value as u32 & 0x7ff);
+        accumulator_bits += 11;
+
+        while accumulator_bits >= 8 {
+            accumulator_bits -= 8;
+            bytes.push((accumulator >> accumulator_bits) as u8);
+        }
+
+        accumulator &= (1 << accumulator_bits) - 1;
+    }
+
+    if accumulator_bits > 0 {
+        bytes.push((accumulator << (8 - accumulator_bits)) as u8);
+    }
+
+    bytes
+}
+
+fn unpack_11_bits(bytes: &[u8]) -> Zeroizing<Vec<u16>> {
+    let mut values = Zeroizing::new(Vec::with_capacity((bytes.len() * 8 + 10) / 11));
+
+    let mut accumulator = 0u32;
+    let mut accumulator_bits = 0;
+
+    for byte in bytes {
+        accumulator = (accumulator << 8) | *byte as u32;
+        accumulator_bits += 8;
+
+        if accumulator_bits >= 11 {
+            accumulator_bits -= 11;
+            values.push((accumulator >> accumulator_bits) as u16 & 0x7ff);
+        }
+
+        accumulator &= (1 << accumulator_bits) - 1;
+    }
+
+    if accumulator_bits > 0 {
+        values.push((accumulator << (11 - accumulator_bits)) as u16 & 0x7ff);
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str =
+        "legal winner thank year wave sausage worth useful legal winner thank yellow";
+
+    fn split(threshold: u8, share_count: u8) -> Vec<SecUtf8> {
+        split_phrase(PHRASE, Language::English, threshold, share_count).unwrap()
+    }
+
+    fn combine(shares: &[SecUtf8]) -> Result<String, String> {
+        combine_phrase(shares, Language::English).map(|e| e.unsecure().to_owned())
+    }
+
+    fn replace_last_word(share: &SecUtf8, f: impl Fn(u16) -> u16) -> SecUtf8 {
+        let wordmap = Language::English.wordmap();
+        let wordlist = Language::English.wordlist();
+
+        let mut words = share.unsecure().split_whitespace().collect::<Vec<_>>();
+        let last = u16::from(wordmap.get_bits(words.last().unwrap()).unwrap());
+        *words.last_mut().unwrap() = wordlist.get_word(f(last).into());
+
+        SecUtf8::from(words.join(" "))
+    }
+
+    #[test]
+    fn round_trip() {
+        for (threshold, share_count) in [(1, 1), (1, 3), (2, 3), (3, 5), (5, 5), (4, 10)] {
+            let shares = split(threshold, share_count);
+            assert_eq!(shares.len(), share_count as usize);
+
+            let first = &shares[..threshold as usize];
+            assert_eq!(combine(first).unwrap(), PHRASE);
+
+            let last = &shares[(share_count - threshold) as usize..];
+            assert_eq!(combine(last).unwrap(), PHRASE);
+
+            assert_eq!(combine(&shares).unwrap(), PHRASE);
+        }
+    }
+
+    #[test]
+    fn invalid_threshold() {
+        assert!(split_phrase(PHRASE, Language::English, 0, 3).is_err());
+        assert!(split_phrase(PHRASE, Language::English, 4, 3).is_err());
+    }
+
+    #[test]
+    fn not_enough_shares() {
+        let shares = split(3, 5);
+
+        assert!(combine(&shares[..2]).is_err());
+        assert!(combine(&[]).is_err());
+    }
+
+    #[test]
+    fn shares_from_different_backups() {
+        let first = split(2, 3);
+        let second = split(2, 3);
+
+        let shares = [first[0].clone(), second[1].clone()];
+
+        assert!(combine(&shares).is_err());
+    }
+
+    #[test]
+    fn duplicate_indices() {
+        let shares = split(2, 3);
+
+        let duplicates = [shares[0].clone(), shares[0].clone()];
+
+        assert!(combine(&duplicates).is_err());
+    }
+
+    #[test]
+    fn corrupted_checksum() {
+        let mut shares = split(2, 3);
+
+        // The highest bit of the last word always belongs to the checksum
+        shares[1] = replace_last_word(&shares[1], |index| index ^ 0x400);
+
+        let share = decode_share(shares[1].unsecure()).unwrap();
+        assert!(!share.is_checksum_valid);
+
+        assert!(combine(&shares[..2]).is_err());
+    }
+
+    #[test]
+    fn extra_words() {
+        let shares = split(2, 3);
+
+        let share = SecUtf8::from(format!("{} abandon", shares[0].unsecure()));
+
+        assert!(decode_share(share.unsecure()).is_err());
+        assert!(combine(&[share, shares[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn zero_threshold_or_index() {
+        let shares = split(2, 3);
+        let share = decode_share(shares[0].unsecure()).unwrap();
+
+        for (threshold, index) in [(0, share.index), (share.threshold, 0)] {
+            let crafted = encode_share(&Share {
+                identifier: share.identifier,
+                threshold,
+                index,
+                value: share.value.clone(),
+            })
+            .unwrap();
+
+            assert!(decode_share(crafted.unsecure()).is_err());
+            assert!(combine(&[crafted, shares[1].clone()]).is_err());
+        }
+    }
+
+    #[test]
+    fn gf_arithmetic() {
+        for a in 0..=255u8 {
+            assert_eq!(gf_mul(a, 1), a);
+            assert_eq!(gf_mul(a, 0), 0);
+
+            for b in 1..=255u8 {
+                assert_eq!(gf_div(gf_mul(a, b), b), a);
+            }
+        }
+    }
+
+    #[test]
+    fn bits_packing() {
+        let values = (0..2048u16).step_by(7).collect::<Vec<_>>();
+
+        let unpacked = unpack_11_bits(&pack_11_bits(&values));
+
+        assert_eq!(&unpacked[..values.len()], values.as_slice());
+    }
+}
@@ -0,0 +1,10 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShamirShareInfo {
+    pub identifier: u16,
+    pub threshold: u8,
+    pub index: u8,
+    pub is_checksum_valid: bool,
+}
@@ -16,6 +16,7 @@ use std::{
     intrinsics::transmute,
     io,
     os::raw::{c_char, c_void},
+    ptr,
     str::FromStr,
     sync::Arc,
 };
@@ -27,9 +28,11 @@ use allo_isolate::{
 use anyhow::Result;
 use lazy_static::lazy_static;
 use nekoton_utils::SimpleClock;
+use secstr::SecUtf8;
 use serde::Serialize;
 use tokio::runtime::{Builder, Runtime};
 use ton_block::MsgAddressInt;
+use zeroize::{Zeroize, Zeroizing};
 
 lazy_static! {
     static ref RUNTIME: io::Result<Runtime> = Builder::new_multi_thread()
@@ -73,6 +76,11 @@ pub unsafe extern "C" fn nt_free_cstring(ptr: *mut c_char) {
     ptr.to_string_from_ptr();
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn nt_free_secret_cstring(ptr: *mut c_char) {
+    CString::from_raw(ptr).into_bytes_with_nul().zeroize();
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase", tag = "type", content = "data")]
 pub enum ExecutionResult<T>
@@ -101,6 +109,48 @@ where
     }
 }
 
+pub trait MatchSecretResult {
+    fn match_secret_result(self) -> *mut c_char;
+}
+
+impl<T> MatchSecretResult for Result<T, String>
+where
+    T: Serialize,
+{
+    fn match_secret_result(self) -> *mut c_char {
+        let result = match self {
+            Ok(ok) => ExecutionResult::Ok(ok),
+            Err(err) => ExecutionResult::Err(err),
+        };
+
+        // Buffer is sized upfront so that no reallocation leaves a copy of the secret
+        // behind
+        let mut counter = ByteCounter::default();
+        serde_json::to_writer(&mut counter, &result).unwrap();
+
+        let mut buffer = Zeroizing::new(Vec::<u8>::with_capacity(counter.0 + 1));
+        serde_json::to_writer(&mut *buffer, &result).unwrap();
+
+        CString::new(std::mem::take(&mut *buffer))
+            .unwrap()
+            .into_raw()
+    }
+}
+
+#[derive(Default)]
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 pub trait HandleError {
     type Output;
 
@@ -179,6 +229,23 @@ impl ToStringFromPtr for *mut c_char {
     }
 }
 
+pub trait ToSecretStringFromPtr {
+    unsafe fn to_secret_string_from_ptr(self) -> SecUtf8;
+}
+
+impl ToSecretStringFromPtr for *mut c_char {
+    unsafe fn to_secret_string_from_ptr(self) -> SecUtf8 {
+        let bytes = CStr::from_ptr(self).to_bytes();
+        let len = bytes.len();
+
+        let secret = SecUtf8::from(std::str::from_utf8(bytes).unwrap());
+
+        ptr::write_bytes(self, 0, len);
+
+        secret
+    }
+}
+
 pub trait ToOptionalStringFromPtr {
     unsafe fn to_optional_string_from_ptr(self) -> Option<String>;
 }